@@ -30,10 +30,11 @@ impl Alu {
                 }
             }
             AluOperation::Subtract => {
-                let source_b: u16 = !source_b + 1;
+                // negative when the exact difference is, so set if less compares unsigned words
+                let negative: bool = source_a < source_b;
+                let source_b: u16 = (!source_b).wrapping_add(1);
                 let result: u16 = source_a.wrapping_add(source_b);
                 let zero: bool = result == 0;
-                let negative: bool = matches!(result & 0b1000000000000000, 1);
                 AluOutput {
                     result,
                     zero,
@@ -1,9 +1,37 @@
+use crate::types::{Breakpoint, Watchpoint};
+
+/// Why the simulator should exit instead of running
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgsError {
+    /// Help was asked for and has been printed
+    Help,
+    /// The arguments can't be used, help has been printed
+    Usage,
+}
+
 #[derive(Clone)]
 pub struct Args {
     pub log_level: simplelog::LevelFilter,
     pub path_to_file: String,
     pub help_set: bool,
     pub breakpoint: u64,
+    pub break_at: Vec<Breakpoint>,
+    pub watch: Vec<Watchpoint>,
+    pub disassemble: bool,
+    pub interactive: bool,
+    pub gdb: Option<String>,
+    pub history: usize,
+    pub compact_dump: bool,
+    pub functional: bool,
+    pub trace: Option<String>,
+    pub vcd: Option<String>,
+    pub stats: bool,
+    pub profile: bool,
+    pub uart: bool,
+    pub timer: bool,
+    pub framebuffer: Option<String>,
+    pub diff: Option<(String, String)>,
+    pub diff_program: Option<String>,
 }
 
 fn log_level_from_string(log_level: &str) -> simplelog::LevelFilter {
@@ -36,6 +64,91 @@ fn parse_arg(arg: &String, mut current_args: Args) -> Option<Args> {
             print_help();
             current_args.help_set = true;
         }
+        x if x.contains("--disassemble") => {
+            current_args.disassemble = true;
+            println!("Disassembling instead of executing");
+        }
+        x if x.contains("--interactive") => {
+            current_args.interactive = true;
+            println!("Starting interactive debugger");
+        }
+        x if x.contains("--break-at=") => {
+            match Breakpoint::from_string(&x.replace("--break-at=", "")) {
+                Some(breakpoint) => {
+                    println!("Breakpoint when {}", breakpoint);
+                    current_args.break_at.push(breakpoint);
+                }
+                None => {
+                    println!("Invalid breakpoint")
+                }
+            }
+        }
+        x if x.contains("--watch=") => match Watchpoint::from_string(&x.replace("--watch=", "")) {
+            Some(watchpoint) => {
+                println!("Watching {}", watchpoint);
+                current_args.watch.push(watchpoint);
+            }
+            None => {
+                println!("Invalid watchpoint")
+            }
+        },
+        x if x.contains("--gdb=") => {
+            let address = x.replace("--gdb=", "");
+            let address = match address.contains(':') {
+                true => address,
+                false => format!("127.0.0.1:{}", address),
+            };
+            println!("Serving GDB on {}", address);
+            current_args.gdb = Some(address);
+        }
+        x if x.contains("--history=") => match x.replace("--history=", "").parse::<usize>() {
+            Ok(history) => {
+                current_args.history = history;
+                println!("Recording {} clock cycles of history", history)
+            }
+            Err(_) => {
+                println!("Invalid history limit")
+            }
+        },
+        x if x.contains("--functional") => {
+            current_args.functional = true;
+            println!("Executing whole instructions at a time");
+        }
+        x if x.contains("--trace=") => {
+            let path = x.replace("--trace=", "");
+            println!("Tracing instructions to {}", path);
+            current_args.trace = Some(path);
+        }
+        x if x.contains("--vcd=") => {
+            let path = x.replace("--vcd=", "");
+            println!("Dumping signals to {}", path);
+            current_args.vcd = Some(path);
+        }
+        x if x.contains("--stats") => {
+            current_args.stats = true;
+            println!("Printing statistics at the end of the run");
+        }
+        x if x.contains("--profile") => {
+            current_args.profile = true;
+            println!("Profiling where clock cycles are spent");
+        }
+        x if x.contains("--uart") => {
+            current_args.uart = true;
+            println!("Mapping a UART on stdin and stdout at 0xFF00");
+        }
+        x if x.contains("--timer") => {
+            current_args.timer = true;
+            println!("Mapping a timer at 0xFF10");
+        }
+        x if x.contains("--framebuffer=") => {
+            let path = x.replace("--framebuffer=", "");
+            println!("Mapping a framebuffer at 0xE000, saving frames to {}", path);
+            current_args.framebuffer = Some(path);
+        }
+        x if x.contains("--compact-dump") => {
+            current_args.compact_dump = true;
+            println!("Writing compact core dumps");
+        }
         x if x.contains("--breakpoint=") => {
             let breakpoint = x.replace("--breakpoint=", "").parse::<u64>();
             match breakpoint {
@@ -67,20 +180,68 @@ fn parse_file_path(path_to_file: &str) -> Option<String> {
 
 fn print_help() {
     println!("Usage: simulator [options]");
+    println!("Runs a program, exiting with the exit code given to its halt instruction");
+    println!("       simulator diff [expected] [actual] [program]");
+    println!("Compares an expected core dump or expectations file with an actual core dump,");
+    println!("exits with status 1 if they differ and 2 if they can't be compared. Differences in");
+    println!("the code of the optional .ayu program are disassembled");
     println!("Options:");
     println!("--help                              - Print this help message");
     println!("--log=[trace|debug|info|warn|error] - Set the log level                       - Default = info");
     println!("--file=[path]                       - Set the path to the file to be executed - Default = ../example_bytecode/basic_addition.ayu");
+    println!(
+        "--interactive                       - Step through the program in an interactive debugger"
+    );
+    println!("--history=[cycles]                  - Record clock cycles so the debugger can step back       - Default = 100000 when interactive");
+    println!("--gdb=[port|address]                - Wait for a GDB remote protocol client to connect and control execution");
+    println!("--compact-dump                      - Write runs of zero words in core.dump on a single line");
+    println!("--trace=[path]                      - Write every instruction executed to a trace file, CSV if path ends in .csv and JSON Lines otherwise");
+    println!("--vcd=[path]                        - Write the FSM state, control signals and pipeline registers of every clock cycle to a VCD file");
+    println!("--stats                             - Print cycle, instruction, branch and memory access counts at the end of the run");
+    println!("--profile                           - Print the instructions and labelled regions taking the most cycles, and an annotated disassembly");
+    println!("--uart                              - Map a UART at 0xFF00 (data), 0xFF01 (status) and 0xFF02 (control) that reads stdin and writes stdout, not with --interactive");
+    println!("--timer                             - Map a timer at 0xFF10 (counter), 0xFF11 (reload), 0xFF12 (status) and 0xFF13 (control) that counts clock cycles");
+    println!("--framebuffer=[path]                - Map a 64x64 framebuffer of RGB565 pixels at 0xE000, saving it to path at halt and to numbered files on frame, PNG if path ends in .png and PPM otherwise");
+    println!("--functional                        - Execute whole instructions at a time instead of modelling every clock cycle");
+    println!("--disassemble                       - Print a disassembly of the file (a .ayu program or core.dump) and exit");
 }
 
-pub fn parse_args() -> Option<Args> {
+pub fn parse_args() -> Result<Args, ArgsError> {
     let args: Vec<String> = std::env::args().skip(1).collect();
     let initial_args: Args = Args {
         log_level: simplelog::LevelFilter::Info,
         path_to_file: String::from("../example_bytecode/basic_addition.ayu"),
         help_set: false,
         breakpoint: u64::MAX,
+        break_at: Vec::new(),
+        watch: Vec::new(),
+        disassemble: false,
+        interactive: false,
+        gdb: None,
+        history: 0,
+        compact_dump: false,
+        functional: false,
+        trace: None,
+        vcd: None,
+        stats: false,
+        profile: false,
+        uart: false,
+        timer: false,
+        framebuffer: None,
+        diff: None,
+        diff_program: None,
     };
+    if args.first().map(String::as_str) == Some("diff") {
+        if args.len() != 3 && args.len() != 4 {
+            print_help();
+            return Err(ArgsError::Usage);
+        }
+        return Ok(Args {
+            diff: Some((args[1].clone(), args[2].clone())),
+            diff_program: args.get(3).cloned(),
+            ..initial_args
+        });
+    }
     let parsed_args: Args = args.iter().fold(initial_args, |current_args, arg| {
         let previous_args: Args = current_args.clone();
         match parse_arg(arg, current_args) {
@@ -89,7 +250,13 @@ pub fn parse_args() -> Option<Args> {
         }
     });
     if parsed_args.help_set {
-        return None;
+        return Err(ArgsError::Help);
+    }
+    // the debugger's commands and the UART's input would both be read from stdin
+    if parsed_args.interactive && parsed_args.uart {
+        eprintln!("--uart can't be used with --interactive, they both read stdin");
+        print_help();
+        return Err(ArgsError::Usage);
     }
-    Some(parsed_args)
+    Ok(parsed_args)
 }
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use log::debug;
+
+use crate::error::SimError;
+use crate::instructions::parse_instruction;
+use crate::types::{InstructionType, Opcode, SpecialOperation};
+
+/// A single statement of source that will produce one or more words of memory
+struct Statement<'a> {
+    line: usize,
+    source: &'a str,
+    text: &'a str,
+}
+
+/// The words of an assembled program along with the address of every label it defines
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub words: Vec<u16>,
+    pub labels: HashMap<String, u16>,
+}
+
+/// Why a statement could not be assembled, and the part of it at fault
+struct StatementError<'a> {
+    part: &'a str,
+    message: String,
+}
+
+/// Assemble AYU source into the words to be loaded into memory, starting at address 0
+///
+/// Each line can hold labels (`loop:`), then either a mnemonic instruction (`add r5, r3, r4`),
+/// a `.word` directive, or a raw hex instruction (`0 5 3 4`). Comments start with `#`.
+/// `halt` takes an optional exit code (`halt 3`) held in the low byte of the instruction
+pub fn assemble(source: &str) -> Result<Vec<u16>, SimError> {
+    Ok(assemble_program(source)?.words)
+}
+
+/// Assemble AYU source, keeping the labels so addresses can be named
+pub fn assemble_program(source: &str) -> Result<Program, SimError> {
+    let mut labels: HashMap<String, u16> = HashMap::new();
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut address: usize = 0;
+
+    // first pass finds the address of every label so they can be referenced before definition
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let mut text = line.split('#').next().unwrap_or("").trim();
+        while let Some((label, rest)) = split_label(text) {
+            if labels.contains_key(label) {
+                return Err(parse_error(
+                    line_number,
+                    line,
+                    label,
+                    format!("label '{}' is already defined", label),
+                ));
+            }
+            if address > 0xFFFF {
+                return Err(SimError::ProgramTooLarge { words: address + 1 });
+            }
+            debug!("Label {} at {:#06X}", label, address);
+            labels.insert(label.to_string(), address as u16);
+            text = rest.trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+        address += statement_size(text);
+        statements.push(Statement {
+            line: line_number,
+            source: line,
+            text,
+        });
+    }
+    if address > 0x10000 {
+        return Err(SimError::ProgramTooLarge { words: address });
+    }
+
+    // second pass encodes every statement now that all labels are known
+    let mut words: Vec<u16> = Vec::with_capacity(address);
+    for statement in statements {
+        encode_statement(statement.text, &labels, &mut words).map_err(|error| {
+            parse_error(statement.line, statement.source, error.part, error.message)
+        })?;
+    }
+    Ok(Program { words, labels })
+}
+
+/// A parse error pointing at part of a source line
+fn parse_error(line: usize, source: &str, part: &str, message: String) -> SimError {
+    let offset: usize = (part.as_ptr() as usize)
+        .saturating_sub(source.as_ptr() as usize)
+        .min(source.len());
+    SimError::Parse {
+        line,
+        column: source.get(..offset).map_or(1, |x| x.chars().count() + 1),
+        message,
+    }
+}
+
+fn fail<T>(part: &str, message: String) -> Result<T, StatementError<'_>> {
+    Err(StatementError { part, message })
+}
+
+/// Split a leading `label:` from a line, if there is one
+fn split_label(text: &str) -> Option<(&str, &str)> {
+    let (label, rest) = text.split_once(':')?;
+    let label = label.trim();
+    let mut chars = label.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+        && chars.all(|x| x.is_ascii_alphanumeric() || x == '_');
+    match valid {
+        true => Some((label, rest)),
+        false => None,
+    }
+}
+
+/// Number of words a statement assembles to
+fn statement_size(text: &str) -> usize {
+    match text.split_once(char::is_whitespace) {
+        Some((".word", operands)) => operands.split(',').count(),
+        _ => 1,
+    }
+}
+
+/// Assemble a single statement, appending the resulting words
+fn encode_statement<'a>(
+    text: &'a str,
+    labels: &HashMap<String, u16>,
+    words: &mut Vec<u16>,
+) -> Result<(), StatementError<'a>> {
+    let (mnemonic, operands) = match text.split_once(char::is_whitespace) {
+        Some((mnemonic, operands)) => (mnemonic, operands.trim()),
+        None => (text, ""),
+    };
+    let operands: Vec<&str> = match operands.is_empty() {
+        true => Vec::new(),
+        false => operands.split(',').map(|x| x.trim()).collect(),
+    };
+    if mnemonic == ".word" {
+        if operands.is_empty() {
+            return fail(mnemonic, String::from(".word needs at least one value"));
+        }
+        for operand in operands {
+            words.push(parse_field(operand, labels, 0xFFFF)?);
+        }
+        return Ok(());
+    }
+    if mnemonic.eq_ignore_ascii_case("halt") {
+        // the exit code is optional and defaults to 0
+        let exit_code: u16 = match operands.len() {
+            0 => 0,
+            _ => {
+                expect_operands(mnemonic, &operands, 1)?;
+                parse_field(operands[0], labels, 0xFF)?
+            }
+        };
+        words.push(0xF100 | exit_code);
+        return Ok(());
+    }
+    if let Some(operation) = SpecialOperation::from_mnemonic(mnemonic) {
+        let register = match operation.has_register() {
+            true => {
+                expect_operands(mnemonic, &operands, 1)?;
+                parse_register(operands[0])?
+            }
+            false => {
+                expect_operands(mnemonic, &operands, 0)?;
+                0
+            }
+        };
+        words.push(0xF000 | (operation.to_u8() as u16) << 8 | register << 4);
+        return Ok(());
+    }
+    let opcode = match Opcode::from_mnemonic(mnemonic) {
+        Some(opcode) => opcode,
+        None if is_raw_word(text) => {
+            match parse_instruction(text) {
+                Ok(Some(word)) => words.push(word),
+                Ok(None) => (),
+                Err(message) => return fail(text, message),
+            }
+            return Ok(());
+        }
+        None => return fail(mnemonic, format!("unknown mnemonic '{}'", mnemonic)),
+    };
+    let nibbles: [u16; 3] = match (InstructionType::from_opcode(&opcode), &opcode) {
+        (_, Opcode::LoadWord) | (_, Opcode::SaveWord) => {
+            expect_operands(mnemonic, &operands, 2)?;
+            let (offset, base) = parse_memory_operand(operands[1], labels)?;
+            [offset, parse_register(operands[0])?, base]
+        }
+        (_, Opcode::SetPcIf) => {
+            expect_operands(mnemonic, &operands, 3)?;
+            [
+                parse_field(operands[0], labels, 0xF)?,
+                parse_register(operands[1])?,
+                parse_register(operands[2])?,
+            ]
+        }
+        (InstructionType::Register, _) => {
+            expect_operands(mnemonic, &operands, 3)?;
+            [
+                parse_register(operands[0])?,
+                parse_register(operands[1])?,
+                parse_register(operands[2])?,
+            ]
+        }
+        (InstructionType::Set, _) => {
+            expect_operands(mnemonic, &operands, 2)?;
+            let byte = parse_field(operands[1], labels, 0xFF)?;
+            [parse_register(operands[0])?, byte >> 4, byte & 0xF]
+        }
+        _ => return fail(mnemonic, format!("unknown mnemonic '{}'", mnemonic)),
+    };
+    let word: u16 = (opcode.to_u8() as u16) << 12 | nibbles[0] << 8 | nibbles[1] << 4 | nibbles[2];
+    debug!("Assembled '{}' to {:#06X}", text, word);
+    words.push(word);
+    Ok(())
+}
+
+/// Whether a statement is an instruction written as up to 4 hex digits
+fn is_raw_word(text: &str) -> bool {
+    let digits: Vec<char> = text.chars().filter(|x| !x.is_whitespace()).collect();
+    !digits.is_empty() && digits.len() <= 4 && digits.iter().all(|x| x.is_ascii_hexdigit())
+}
+
+fn expect_operands<'a>(
+    mnemonic: &'a str,
+    operands: &[&str],
+    count: usize,
+) -> Result<(), StatementError<'a>> {
+    match operands.len() == count {
+        true => Ok(()),
+        false => fail(
+            mnemonic,
+            format!(
+                "'{}' takes {} operands but {} were given",
+                mnemonic,
+                count,
+                operands.len()
+            ),
+        ),
+    }
+}
+
+/// Parse a register name, `r0` to `r15` or `pc` for `r1`
+fn parse_register(operand: &str) -> Result<u16, StatementError<'_>> {
+    let name = operand.to_lowercase();
+    if name == "pc" {
+        return Ok(1);
+    }
+    match name.strip_prefix('r').map(|x| x.parse::<u16>()) {
+        Some(Ok(register)) if register < 16 => Ok(register),
+        _ => fail(operand, format!("'{}' is not a register", operand)),
+    }
+}
+
+/// Parse a memory operand of the form `offset(register)`, the offset may be omitted
+fn parse_memory_operand<'a>(
+    operand: &'a str,
+    labels: &HashMap<String, u16>,
+) -> Result<(u16, u16), StatementError<'a>> {
+    let (offset, register) = match operand.strip_suffix(')').and_then(|x| x.split_once('(')) {
+        Some(parts) => parts,
+        None => {
+            return fail(
+                operand,
+                format!("'{}' is not of the form offset(register)", operand),
+            )
+        }
+    };
+    let offset = match offset.trim().is_empty() {
+        true => 0,
+        false => parse_field(offset.trim(), labels, 0xF)?,
+    };
+    Ok((offset, parse_register(register.trim())?))
+}
+
+/// Parse a number or label, checking it fits in a field with the given maximum
+fn parse_field<'a>(
+    operand: &'a str,
+    labels: &HashMap<String, u16>,
+    max: u16,
+) -> Result<u16, StatementError<'a>> {
+    let error = |message: String| StatementError {
+        part: operand,
+        message,
+    };
+    let value: u32 = if let Some(hex) = operand.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16)
+            .map_err(|_| error(format!("invalid hex number '{}'", operand)))?
+    } else if let Some(binary) = operand.strip_prefix("0b") {
+        u32::from_str_radix(binary, 2)
+            .map_err(|_| error(format!("invalid binary number '{}'", operand)))?
+    } else if operand.starts_with(|x: char| x.is_ascii_digit()) {
+        operand
+            .parse::<u32>()
+            .map_err(|_| error(format!("invalid number '{}'", operand)))?
+    } else {
+        *labels
+            .get(operand)
+            .ok_or_else(|| error(format!("undefined label '{}'", operand)))? as u32
+    };
+    match value <= max as u32 {
+        true => Ok(value as u16),
+        false => Err(error(format!(
+            "'{}' ({:#X}) does not fit in a field with maximum {:#X}",
+            operand, value, max
+        ))),
+    }
+}
@@ -0,0 +1,151 @@
+use log::debug;
+
+use crate::error::SimError;
+use crate::frame::Frame;
+
+/// A peripheral that answers reads and writes to a range of addresses. Offsets are from the
+/// start of the range the device is mapped at
+pub trait Device {
+    /// Name shown in logs and errors
+    fn name(&self) -> &str;
+
+    /// Number of words of address space the device takes up
+    fn size(&self) -> usize;
+
+    /// Read a word, as an instruction would. Reads may have side effects such as taking
+    /// a character from a receive buffer
+    fn read(&mut self, offset: u16) -> u16;
+
+    /// Read a word without any side effects, for debuggers and watchpoints
+    fn peek(&self, offset: u16) -> u16;
+
+    fn write(&mut self, offset: u16, value: u16);
+
+    /// Advance by a clock cycle, devices that don't keep time ignore it
+    fn tick(&mut self) {}
+
+    /// Whether the device is requesting an interrupt, checked before each instruction
+    fn interrupt(&mut self) -> bool {
+        false
+    }
+
+    /// The image shown, for devices that are displays
+    fn frame(&self) -> Option<Frame> {
+        None
+    }
+
+    /// Whether reads, writes or clock cycles can change the device. Undo history doesn't
+    /// record device state, so stepping back is refused while one that can is mapped
+    fn is_stateful(&self) -> bool {
+        true
+    }
+}
+
+/// A device and the addresses it is mapped to
+struct Mapping {
+    start: u16,
+    /// Last address included
+    end: u16,
+    device: Box<dyn Device>,
+}
+
+/// Routes memory accesses to the device mapped at an address. Addresses with no device mapped
+/// are left to the processor's RAM
+#[derive(Default)]
+pub struct Bus {
+    mappings: Vec<Mapping>,
+}
+
+impl Bus {
+    pub fn new() -> Bus {
+        Bus::default()
+    }
+
+    /// Map a device at a start address, it must fit in memory without overlapping another
+    pub fn map(&mut self, start: u16, device: Box<dyn Device>) -> Result<(), SimError> {
+        let end: usize = start as usize + device.size();
+        let overlaps = self
+            .mappings
+            .iter()
+            .any(|x| (start as usize) <= x.end as usize && end > x.start as usize);
+        if device.size() == 0 || end > 0x10000 || overlaps {
+            return Err(SimError::DeviceMapping {
+                name: device.name().to_string(),
+                start,
+                size: device.size(),
+            });
+        }
+        debug!(
+            "Mapped {} at {:#06X}-{:#06X}",
+            device.name(),
+            start,
+            end - 1
+        );
+        self.mappings.push(Mapping {
+            start,
+            end: (end - 1) as u16,
+            device,
+        });
+        Ok(())
+    }
+
+    fn mapping(&self, address: u16) -> Option<&Mapping> {
+        self.mappings
+            .iter()
+            .find(|x| x.start <= address && address <= x.end)
+    }
+
+    fn mapping_mut(&mut self, address: u16) -> Option<&mut Mapping> {
+        self.mappings
+            .iter_mut()
+            .find(|x| x.start <= address && address <= x.end)
+    }
+
+    /// Read from the device at an address, None if there isn't one
+    pub fn read(&mut self, address: u16) -> Option<u16> {
+        let mapping: &mut Mapping = self.mapping_mut(address)?;
+        Some(mapping.device.read(address - mapping.start))
+    }
+
+    /// Read from the device at an address without side effects, None if there isn't one
+    pub fn peek(&self, address: u16) -> Option<u16> {
+        let mapping: &Mapping = self.mapping(address)?;
+        Some(mapping.device.peek(address - mapping.start))
+    }
+
+    /// Write to the device at an address, returns false if there isn't one
+    pub fn write(&mut self, address: u16, value: u16) -> bool {
+        match self.mapping_mut(address) {
+            Some(mapping) => {
+                mapping.device.write(address - mapping.start, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Advance every device by a clock cycle
+    pub fn tick(&mut self) {
+        self.mappings.iter_mut().for_each(|x| x.device.tick());
+    }
+
+    /// Whether any device is requesting an interrupt
+    pub fn interrupt(&mut self) -> bool {
+        self.mappings.iter_mut().any(|x| x.device.interrupt())
+    }
+
+    /// The image shown by the first display device mapped, None if there isn't one
+    pub fn frame(&self) -> Option<Frame> {
+        self.mappings.iter().find_map(|x| x.device.frame())
+    }
+
+    /// Whether any device mapped can change state
+    pub fn is_stateful(&self) -> bool {
+        self.mappings.iter().any(|x| x.device.is_stateful())
+    }
+
+    /// Whether a device is mapped at an address
+    pub fn is_mapped(&self, address: u16) -> bool {
+        self.mapping(address).is_some()
+    }
+}
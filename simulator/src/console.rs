@@ -0,0 +1,65 @@
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+
+use crate::error::SimError;
+
+/// Value read by an input instruction once there is no input left
+pub const END_OF_INPUT: u16 = 0xFFFF;
+
+/// The streams console special instructions read from and write to, stdin and stdout
+/// unless replaced
+pub struct Console {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+}
+
+impl Console {
+    pub fn new(input: Box<dyn BufRead>, output: Box<dyn Write>) -> Console {
+        Console { input, output }
+    }
+
+    /// A console on the standard streams
+    pub fn stdio() -> Console {
+        Console::new(
+            Box::new(BufReader::new(std::io::stdin())),
+            Box::new(std::io::stdout()),
+        )
+    }
+
+    /// Write text, flushing straight away so prompts appear before input is read
+    pub fn write(&mut self, text: &str) -> Result<(), SimError> {
+        self.output.write_all(text.as_bytes())?;
+        self.output.flush()?;
+        Ok(())
+    }
+
+    /// Read a single byte, END_OF_INPUT if there is none left
+    pub fn read_char(&mut self) -> Result<u16, SimError> {
+        let byte: Option<u8> = self.input.fill_buf()?.first().copied();
+        match byte {
+            Some(byte) => {
+                self.input.consume(1);
+                Ok(byte as u16)
+            }
+            None => Ok(END_OF_INPUT),
+        }
+    }
+
+    /// Read a line holding a decimal number, negative numbers are read as two's complement.
+    /// END_OF_INPUT if there is no line left
+    pub fn read_decimal(&mut self) -> Result<u16, SimError> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(END_OF_INPUT);
+        }
+        let text: &str = line.trim();
+        match text.parse::<i32>() {
+            Ok(number) if (i16::MIN as i32..=u16::MAX as i32).contains(&number) => {
+                Ok(number as u16)
+            }
+            _ => Err(SimError::Io(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("'{}' is not a 16 bit decimal number", text),
+            ))),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt::Write;
+
+use time::OffsetDateTime;
+
+use crate::error::SimError;
+
+/// Registers and memory as written to a core dump
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreDump {
+    pub clock_cycle: u64,
+    pub registers: [u16; 16],
+    pub memory: Vec<u16>,
+}
+
+impl CoreDump {
+    /// Format as a core.dump file with every word of memory on it's own line
+    pub fn to_text(&self) -> String {
+        let mut dump = self.header();
+        for (i, memory) in self.memory.iter().enumerate() {
+            writeln!(dump, "M{:#06X}: {:#06X}", i, memory).unwrap();
+        }
+        dump
+    }
+
+    /// Format as a core.dump file where runs of zero words are written as a single
+    /// `M0x0010-0xFFFF: 0x0000` line
+    pub fn to_compact_text(&self) -> String {
+        let mut dump = self.header();
+        let mut address: usize = 0;
+        while address < self.memory.len() {
+            let zero_run = self.memory[address..]
+                .iter()
+                .take_while(|x| **x == 0)
+                .count();
+            if zero_run > 1 {
+                let end = address + zero_run - 1;
+                writeln!(dump, "M{:#06X}-{:#06X}: 0x0000", address, end).unwrap();
+                address += zero_run;
+                continue;
+            }
+            writeln!(dump, "M{:#06X}: {:#06X}", address, self.memory[address]).unwrap();
+            address += 1;
+        }
+        dump
+    }
+
+    /// Everything before the memory lines, shared by both formats
+    fn header(&self) -> String {
+        let mut dump = format!("Core dump at time: {:#?}\n", OffsetDateTime::now_utc());
+        writeln!(dump, "Clock cycle: {:#?}", self.clock_cycle).unwrap();
+        dump.push_str("\nRegisters:\n");
+        for (i, register) in self.registers.iter().enumerate() {
+            writeln!(dump, "R{:#02X}: {:#06X}", i, register).unwrap();
+        }
+        dump.push_str("\nMemory:\n");
+        dump
+    }
+
+    /// Read a core dump in either the full or compact format
+    pub fn parse(text: &str) -> Result<CoreDump, SimError> {
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.starts_with("Core dump at time: ") => (),
+            _ => return Err(SimError::parse(1, "not a core dump")),
+        }
+        let mut dump = CoreDump {
+            clock_cycle: 0,
+            registers: [0; 16],
+            memory: vec![0; 65536],
+        };
+        let mut registers_seen: [bool; 16] = [false; 16];
+        let mut memory_seen: usize = 0;
+        for (i, line) in lines {
+            let error = |message: &str| SimError::parse(i + 1, message);
+            if line.is_empty() || line == "Registers:" || line == "Memory:" {
+                continue;
+            }
+            let (name, value) = line
+                .split_once(": ")
+                .ok_or_else(|| error("expected 'name: value'"))?;
+            let word = || parse_word(value).ok_or_else(|| error("invalid word"));
+            match name {
+                "Clock cycle" => {
+                    dump.clock_cycle = value.parse().map_err(|_| error("invalid clock cycle"))?
+                }
+                x if x.starts_with('R') => match parse_word(&x[1..]) {
+                    Some(register) if register < 16 => {
+                        dump.registers[register as usize] = word()?;
+                        registers_seen[register as usize] = true;
+                    }
+                    _ => return Err(error("invalid register")),
+                },
+                x if x.starts_with('M') => {
+                    let (start, end) = match x[1..].split_once('-') {
+                        Some((start, end)) => (parse_word(start), parse_word(end)),
+                        None => (parse_word(&x[1..]), parse_word(&x[1..])),
+                    };
+                    let (start, end) = match (start, end) {
+                        (Some(start), Some(end)) if start <= end => (start, end),
+                        _ => return Err(error("invalid address")),
+                    };
+                    let value = word()?;
+                    dump.memory[start as usize..=end as usize].fill(value);
+                    memory_seen += (end - start) as usize + 1;
+                }
+                _ => return Err(error(&format!("unexpected line '{}'", line))),
+            }
+        }
+        let end: usize = text.lines().count();
+        if registers_seen.contains(&false) {
+            return Err(SimError::parse(end, "core dump is missing registers"));
+        }
+        if memory_seen != dump.memory.len() {
+            return Err(SimError::parse(
+                end,
+                format!(
+                    "core dump covers {} words of memory, expected {}",
+                    memory_seen,
+                    dump.memory.len()
+                ),
+            ));
+        }
+        Ok(dump)
+    }
+
+    /// Read a core dump from a file
+    pub fn load(path: &str) -> Result<CoreDump, SimError> {
+        let text = std::fs::read_to_string(path)?;
+        CoreDump::parse(&text)
+    }
+}
+
+/// Parse a 0x prefixed hex word, as written in core dumps, snapshots and expectations files
+pub(crate) fn parse_word(text: &str) -> Option<u16> {
+    u16::from_str_radix(text.strip_prefix("0x")?, 16).ok()
+}
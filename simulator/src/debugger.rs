@@ -0,0 +1,372 @@
+use std::io::{BufRead, Write};
+
+use crate::disassembler::disassemble;
+use crate::processor::Processor;
+use crate::snapshot::Snapshot;
+use crate::types::{parse_number, Breakpoint, RunState, Watchpoint};
+
+/// Clock cycles of history kept for stepping back, unless the processor already records some
+const DEFAULT_HISTORY_LIMIT: usize = 100_000;
+
+const HELP: &str = "Commands:
+step [n]           (s)  - Run n clock cycles                        - Default = 1
+stepi [n]          (si) - Run n instructions                        - Default = 1
+continue           (c)  - Run until a breakpoint or the program ends
+back [n]                - Step back n clock cycles                  - Default = 1
+backi [n]               - Step back n instructions                  - Default = 1
+rewind cycle            - Step back to a clock cycle
+break [condition]  (b)  - Set a breakpoint, or list breakpoints
+                          condition is a PC address (0x4), mnemonic (lw) or register test (r3 == 0x10)
+delete [condition] (d)  - Clear a breakpoint, or all breakpoints
+watch [target]     (w)  - Stop when a register (r3) or memory address is accessed, or list watchpoints
+                          addresses watch writes (0x10) unless reads (0x10:r) or both (0x10:rw) are given
+unwatch [target]        - Clear a watchpoint, or all watchpoints
+registers          (r)  - Print all registers
+memory address [n] (m)  - Print n words of memory from address      - Default = 1
+set [rN|address] value  - Overwrite a register or word of memory
+state                   - Print the FSM state, control signals and pipeline registers
+save path               - Save a snapshot of the processor to a file
+load path               - Restore the processor from a snapshot file
+help               (h)  - Print this help message
+quit               (q)  - Exit the debugger
+An empty line repeats the last command";
+
+/// Interactive debugger that steps a processor under user control
+pub struct Debugger {
+    processor: Processor,
+    /// Clock cycle the program finished or stopped with an error at
+    halt_cycle: Option<u64>,
+}
+
+impl Debugger {
+    pub fn new(mut processor: Processor) -> Debugger {
+        if processor.history_limit() == 0 {
+            processor.set_history_limit(DEFAULT_HISTORY_LIMIT);
+        }
+        Debugger {
+            processor,
+            halt_cycle: None,
+        }
+    }
+
+    /// Whether the processor is at or past the point the program finished
+    fn halted(&self) -> bool {
+        self.halt_cycle
+            .is_some_and(|cycle| self.processor.clock_cycle() >= cycle)
+    }
+
+    /// The processor being debugged
+    pub fn processor(&self) -> &Processor {
+        &self.processor
+    }
+
+    /// Read commands from input until it ends or `quit` is entered, writing results to output
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+        writeln!(output, "Type 'help' for a list of commands")?;
+        let mut last_command: String = String::new();
+        write!(output, "(ayu) ")?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            let command: String = match line.trim().is_empty() {
+                true => last_command.clone(),
+                false => line.trim().to_string(),
+            };
+            if !self.execute(&command, &mut output)? {
+                return Ok(());
+            }
+            last_command = command;
+            write!(output, "(ayu) ")?;
+            output.flush()?;
+        }
+        writeln!(output)?;
+        Ok(())
+    }
+
+    /// Execute a single command, returns false if the debugger should exit
+    fn execute(&mut self, command: &str, output: &mut impl Write) -> std::io::Result<bool> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let argument = |i: usize| words.get(i).map(|x| parse_number(x));
+        match words.first().copied().unwrap_or("") {
+            "" => (),
+            "step" | "s" => match argument(1).unwrap_or(Some(1)) {
+                Some(count) => self.step(count, false, output)?,
+                None => writeln!(output, "Invalid cycle count")?,
+            },
+            "stepi" | "si" => match argument(1).unwrap_or(Some(1)) {
+                Some(count) => self.step(count, true, output)?,
+                None => writeln!(output, "Invalid instruction count")?,
+            },
+            "continue" | "c" => self.step(u32::MAX, true, output)?,
+            "back" => match argument(1).unwrap_or(Some(1)) {
+                Some(count) => self.step_back(count, false, output)?,
+                None => writeln!(output, "Invalid cycle count")?,
+            },
+            "backi" => match argument(1).unwrap_or(Some(1)) {
+                Some(count) => self.step_back(count, true, output)?,
+                None => writeln!(output, "Invalid instruction count")?,
+            },
+            "rewind" => match argument(1) {
+                Some(Some(cycle)) => {
+                    if !self.processor.rewind_to(cycle as u64) {
+                        self.print_history_ended(output)?;
+                    }
+                    self.print_location(output)?;
+                }
+                _ => writeln!(output, "Usage: rewind cycle")?,
+            },
+            "break" | "b" => match words.len() {
+                1 => {
+                    for breakpoint in self.processor.breakpoints() {
+                        writeln!(output, "Breakpoint when {}", breakpoint)?;
+                    }
+                }
+                _ => match Breakpoint::from_string(&words[1..].join(" ")) {
+                    Some(breakpoint) => {
+                        writeln!(output, "Breakpoint set when {}", breakpoint)?;
+                        self.processor.add_breakpoint(breakpoint);
+                    }
+                    None => writeln!(output, "Invalid breakpoint")?,
+                },
+            },
+            "delete" | "d" => match words.len() {
+                1 => {
+                    self.processor.clear_breakpoints();
+                    writeln!(output, "Cleared all breakpoints")?;
+                }
+                _ => match Breakpoint::from_string(&words[1..].join(" ")) {
+                    Some(breakpoint) if self.processor.remove_breakpoint(&breakpoint) => {
+                        writeln!(output, "Cleared breakpoint when {}", breakpoint)?;
+                    }
+                    _ => writeln!(output, "No such breakpoint")?,
+                },
+            },
+            "watch" | "w" => match words.len() {
+                1 => {
+                    for watchpoint in self.processor.watchpoints() {
+                        writeln!(output, "Watching {}", watchpoint)?;
+                    }
+                }
+                _ => match Watchpoint::from_string(&words[1..].join(" ")) {
+                    Some(watchpoint) => {
+                        writeln!(output, "Watching {}", watchpoint)?;
+                        self.processor.add_watchpoint(watchpoint);
+                    }
+                    None => writeln!(output, "Invalid watchpoint")?,
+                },
+            },
+            "unwatch" => match words.len() {
+                1 => {
+                    self.processor.clear_watchpoints();
+                    writeln!(output, "Cleared all watchpoints")?;
+                }
+                _ => match Watchpoint::from_string(&words[1..].join(" ")) {
+                    Some(watchpoint) if self.processor.remove_watchpoint(&watchpoint) => {
+                        writeln!(output, "Stopped watching {}", watchpoint)?;
+                    }
+                    _ => writeln!(output, "No such watchpoint")?,
+                },
+            },
+            "registers" | "r" => self.print_registers(output)?,
+            "memory" | "m" => match (argument(1), argument(2).unwrap_or(Some(1))) {
+                (Some(Some(address)), Some(count)) if address <= 0xFFFF => {
+                    self.print_memory(address as u16, count, output)?
+                }
+                _ => writeln!(output, "Usage: memory address [n]")?,
+            },
+            "set" => self.set(&words, output)?,
+            "state" => self.print_state(output)?,
+            "save" => match words.get(1) {
+                Some(path) => match self.processor.snapshot().save(path) {
+                    Ok(()) => writeln!(output, "Saved snapshot to {}", path)?,
+                    Err(error) => writeln!(output, "Could not save snapshot: {}", error)?,
+                },
+                None => writeln!(output, "Usage: save path")?,
+            },
+            "load" => match words
+                .get(1)
+                .map(|path| Snapshot::load(path).and_then(|x| self.processor.restore(&x)))
+            {
+                Some(Ok(())) => {
+                    self.halt_cycle = None;
+                    self.print_location(output)?;
+                }
+                Some(Err(error)) => writeln!(output, "Could not load snapshot: {}", error)?,
+                None => writeln!(output, "Usage: load path")?,
+            },
+            "help" | "h" => writeln!(output, "{}", HELP)?,
+            "quit" | "q" => return Ok(false),
+            x => writeln!(output, "Unknown command '{}', type 'help' for a list", x)?,
+        }
+        Ok(true)
+    }
+
+    /// Run a number of clock cycles or instructions, stopping early at a breakpoint
+    /// or the end of the program
+    fn step(
+        &mut self,
+        count: u32,
+        by_instruction: bool,
+        output: &mut impl Write,
+    ) -> std::io::Result<()> {
+        if self.halted() {
+            writeln!(output, "The program has finished")?;
+            return Ok(());
+        }
+        for _ in 0..count {
+            let result = match by_instruction {
+                true => self.processor.step_instruction(),
+                false => self.processor.run(),
+            };
+            let running: RunState = match result {
+                Ok(running) => running,
+                Err(error) => {
+                    self.halt_cycle = Some(self.processor.clock_cycle());
+                    writeln!(output, "The program stopped with an error: {}", error)?;
+                    break;
+                }
+            };
+            if let Some(hit) = self.processor.watchpoint_hit() {
+                writeln!(
+                    output,
+                    "Watchpoint {} hit by {} at {:#06X}: {:#06X} -> {:#06X}",
+                    hit.watchpoint,
+                    disassemble(hit.instruction),
+                    hit.pc,
+                    hit.old_value,
+                    hit.new_value
+                )?;
+                break;
+            }
+            if let Some(breakpoint) = self.processor.hit_breakpoint() {
+                writeln!(output, "Reached breakpoint when {}", breakpoint)?;
+                break;
+            }
+            if let RunState::Stop(reason) = running {
+                self.halt_cycle = Some(self.processor.clock_cycle());
+                writeln!(output, "The program has finished, {}", reason)?;
+                break;
+            }
+        }
+        self.print_location(output)
+    }
+
+    /// Undo a number of clock cycles or instructions
+    fn step_back(
+        &mut self,
+        count: u32,
+        by_instruction: bool,
+        output: &mut impl Write,
+    ) -> std::io::Result<()> {
+        for _ in 0..count {
+            let stepped: bool = match by_instruction {
+                true => self.processor.step_back_instruction(),
+                false => self.processor.step_back(),
+            };
+            if !stepped {
+                self.print_history_ended(output)?;
+                break;
+            }
+        }
+        self.print_location(output)
+    }
+
+    /// Explain why there is no more history to step back through
+    fn print_history_ended(&self, output: &mut impl Write) -> std::io::Result<()> {
+        match self.processor.has_stateful_devices() {
+            true => writeln!(
+                output,
+                "Can't step back while a device that keeps state is mapped"
+            ),
+            false => writeln!(output, "Reached the start of the recorded history"),
+        }
+    }
+
+    /// Overwrite a register or memory address
+    fn set(&mut self, words: &[&str], output: &mut impl Write) -> std::io::Result<()> {
+        let value = match words.get(2).and_then(|x| parse_number(x)) {
+            Some(value) if value <= 0xFFFF => value as u16,
+            _ => return writeln!(output, "Usage: set [rN|address] value"),
+        };
+        let target = words.get(1).copied().unwrap_or("");
+        if let Some(register) = target.strip_prefix('r').and_then(parse_number) {
+            if register < 16 {
+                self.processor.set_register(register as usize, value);
+                return writeln!(output, "R{:#02X} = {:#06X}", register, value);
+            }
+        }
+        match parse_number(target) {
+            Some(address) if address <= 0xFFFF => {
+                self.processor.set_memory(address as u16, value);
+                writeln!(output, "M{:#06X} = {:#06X}", address, value)
+            }
+            _ => writeln!(output, "Usage: set [rN|address] value"),
+        }
+    }
+
+    fn print_location(&self, output: &mut impl Write) -> std::io::Result<()> {
+        let pc: u16 = self.processor.registers()[1];
+        writeln!(
+            output,
+            "Cycle {}, {:?}, PC {:#06X}: {}",
+            self.processor.clock_cycle(),
+            self.processor.state(),
+            pc,
+            disassemble(self.processor.peek(pc))
+        )
+    }
+
+    fn print_registers(&self, output: &mut impl Write) -> std::io::Result<()> {
+        for (i, row) in self.processor.registers().chunks(4).enumerate() {
+            let line: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(j, register)| format!("R{:<2}: {:#06X}", i * 4 + j, register))
+                .collect();
+            writeln!(output, "{}", line.join("  "))?;
+        }
+        let interrupts: &str = match self.processor.interrupts_enabled() {
+            true => "enabled",
+            false => "disabled",
+        };
+        writeln!(
+            output,
+            "EPC: {:#06X}  interrupts {}",
+            self.processor.epc(),
+            interrupts
+        )?;
+        Ok(())
+    }
+
+    fn print_memory(
+        &self,
+        address: u16,
+        count: u32,
+        output: &mut impl Write,
+    ) -> std::io::Result<()> {
+        for address in (address as usize..0x10000).take(count as usize) {
+            let word: u16 = self.processor.peek(address as u16);
+            writeln!(
+                output,
+                "{:#06X}: {:#06X}  {}",
+                address,
+                word,
+                disassemble(word)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn print_state(&self, output: &mut impl Write) -> std::io::Result<()> {
+        self.print_location(output)?;
+        let instruction_register: u16 = self.processor.instruction_register();
+        writeln!(
+            output,
+            "Instruction register: {:#06X}  {}",
+            instruction_register,
+            disassemble(instruction_register)
+        )?;
+        writeln!(output, "{:#?}", self.processor.pipeline_registers())?;
+        writeln!(output, "{:#?}", self.processor.control_signals())
+    }
+}
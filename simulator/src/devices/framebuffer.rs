@@ -0,0 +1,61 @@
+use crate::bus::Device;
+use crate::frame::Frame;
+
+/// Address the framebuffer is normally mapped at
+pub const FRAMEBUFFER_BASE: u16 = 0xE000;
+pub const FRAMEBUFFER_WIDTH: usize = 64;
+pub const FRAMEBUFFER_HEIGHT: usize = 64;
+
+/// A grid of pixels with one RGB565 colour word each, in rows from the top left
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u16>,
+}
+
+impl Framebuffer {
+    /// A framebuffer of a size, all black
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Framebuffer {
+        Framebuffer::new(FRAMEBUFFER_WIDTH, FRAMEBUFFER_HEIGHT)
+    }
+}
+
+impl Device for Framebuffer {
+    fn name(&self) -> &str {
+        "framebuffer"
+    }
+
+    fn size(&self) -> usize {
+        self.pixels.len()
+    }
+
+    fn read(&mut self, offset: u16) -> u16 {
+        self.pixels[offset as usize]
+    }
+
+    fn peek(&self, offset: u16) -> u16 {
+        self.pixels[offset as usize]
+    }
+
+    fn write(&mut self, offset: u16, value: u16) {
+        self.pixels[offset as usize] = value;
+    }
+
+    fn frame(&self) -> Option<Frame> {
+        Some(Frame {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.clone(),
+        })
+    }
+}
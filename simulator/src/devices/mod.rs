@@ -0,0 +1,17 @@
+mod framebuffer;
+mod ram;
+mod rom;
+mod timer;
+mod uart;
+
+pub use framebuffer::{Framebuffer, FRAMEBUFFER_BASE, FRAMEBUFFER_HEIGHT, FRAMEBUFFER_WIDTH};
+pub use ram::Ram;
+pub use rom::Rom;
+pub use timer::{
+    Timer, TIMER_BASE, TIMER_CONTROL, TIMER_COUNTER, TIMER_EXPIRED, TIMER_INTERRUPT, TIMER_RELOAD,
+    TIMER_STATUS,
+};
+pub use uart::{
+    Uart, RX_AVAILABLE, RX_ENDED, RX_INTERRUPT, TX_READY, UART_BASE, UART_CONTROL, UART_DATA,
+    UART_STATUS,
+};
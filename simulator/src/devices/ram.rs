@@ -0,0 +1,37 @@
+use crate::bus::Device;
+
+/// A block of read-write memory, separate from the processor's own RAM
+pub struct Ram {
+    words: Vec<u16>,
+}
+
+impl Ram {
+    /// RAM of a number of words, all 0
+    pub fn new(size: usize) -> Ram {
+        Ram {
+            words: vec![0; size],
+        }
+    }
+}
+
+impl Device for Ram {
+    fn name(&self) -> &str {
+        "RAM"
+    }
+
+    fn size(&self) -> usize {
+        self.words.len()
+    }
+
+    fn read(&mut self, offset: u16) -> u16 {
+        self.words[offset as usize]
+    }
+
+    fn peek(&self, offset: u16) -> u16 {
+        self.words[offset as usize]
+    }
+
+    fn write(&mut self, offset: u16, value: u16) {
+        self.words[offset as usize] = value;
+    }
+}
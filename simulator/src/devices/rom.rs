@@ -0,0 +1,43 @@
+use log::warn;
+
+use crate::bus::Device;
+
+/// Read-only memory holding fixed contents, writes are ignored
+pub struct Rom {
+    words: Vec<u16>,
+}
+
+impl Rom {
+    pub fn new(words: Vec<u16>) -> Rom {
+        Rom { words }
+    }
+}
+
+impl Device for Rom {
+    fn name(&self) -> &str {
+        "ROM"
+    }
+
+    fn size(&self) -> usize {
+        self.words.len()
+    }
+
+    fn read(&mut self, offset: u16) -> u16 {
+        self.words[offset as usize]
+    }
+
+    fn peek(&self, offset: u16) -> u16 {
+        self.words[offset as usize]
+    }
+
+    fn write(&mut self, offset: u16, value: u16) {
+        warn!(
+            "Ignored write of {:#06X} to ROM offset {:#06X}",
+            value, offset
+        );
+    }
+
+    fn is_stateful(&self) -> bool {
+        false
+    }
+}
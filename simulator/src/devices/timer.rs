@@ -0,0 +1,77 @@
+use crate::bus::Device;
+
+/// Address the timer is normally mapped at
+pub const TIMER_BASE: u16 = 0xFF10;
+/// Offset of the counter, incremented every clock cycle. Writes set it
+pub const TIMER_COUNTER: u16 = 0;
+/// Offset of the reload register. When it is not 0 the counter returns to 0 on reaching it
+/// and the timer expires, otherwise the counter runs freely and wraps around
+pub const TIMER_RELOAD: u16 = 1;
+/// Offset of the status register, any write clears it
+pub const TIMER_STATUS: u16 = 2;
+/// Offset of the control register
+pub const TIMER_CONTROL: u16 = 3;
+
+/// Status bit set when the counter reaches the reload value, until it is cleared
+pub const TIMER_EXPIRED: u16 = 0b1;
+/// Control bit requesting an interrupt while the timer has expired
+pub const TIMER_INTERRUPT: u16 = 0b1;
+
+/// Counts clock cycles so programs can measure elapsed time and wait for a delay
+#[derive(Debug, Clone, Default)]
+pub struct Timer {
+    counter: u16,
+    reload: u16,
+    expired: bool,
+    control: u16,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer::default()
+    }
+}
+
+impl Device for Timer {
+    fn name(&self) -> &str {
+        "timer"
+    }
+
+    fn size(&self) -> usize {
+        4
+    }
+
+    fn read(&mut self, offset: u16) -> u16 {
+        self.peek(offset)
+    }
+
+    fn peek(&self, offset: u16) -> u16 {
+        match offset {
+            TIMER_COUNTER => self.counter,
+            TIMER_RELOAD => self.reload,
+            TIMER_STATUS => self.expired as u16 * TIMER_EXPIRED,
+            _ => self.control,
+        }
+    }
+
+    fn write(&mut self, offset: u16, value: u16) {
+        match offset {
+            TIMER_COUNTER => self.counter = value,
+            TIMER_RELOAD => self.reload = value,
+            TIMER_STATUS => self.expired = false,
+            _ => self.control = value,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.counter = self.counter.wrapping_add(1);
+        if self.reload != 0 && self.counter == self.reload {
+            self.counter = 0;
+            self.expired = true;
+        }
+    }
+
+    fn interrupt(&mut self) -> bool {
+        self.expired && self.control & TIMER_INTERRUPT != 0
+    }
+}
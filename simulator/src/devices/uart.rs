@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use log::{error, warn};
+
+use crate::bus::Device;
+
+/// Address the UART is normally mapped at
+pub const UART_BASE: u16 = 0xFF00;
+/// Offset of the data register, reads take a received byte and writes transmit one
+pub const UART_DATA: u16 = 0;
+/// Offset of the read only status register
+pub const UART_STATUS: u16 = 1;
+/// Offset of the control register
+pub const UART_CONTROL: u16 = 2;
+
+/// Status bit set while a received byte is waiting to be read
+pub const RX_AVAILABLE: u16 = 0b001;
+/// Status bit set when a byte can be transmitted, always the case
+pub const TX_READY: u16 = 0b010;
+/// Status bit set once the input has ended and every byte has been read
+pub const RX_ENDED: u16 = 0b100;
+
+/// Control bit requesting an interrupt while a received byte is waiting or once the input
+/// has ended
+pub const RX_INTERRUPT: u16 = 0b1;
+
+/// Most bytes the input thread reads at a time
+const READ_SIZE: usize = 256;
+
+/// A serial console with a data, status and control register, transmitting to an output
+/// stream and receiving from an input stream. The input is read on a thread of it's own so
+/// the program never waits for it
+pub struct Uart {
+    /// Bytes read from the input, disconnected once the input has ended
+    input: Receiver<Vec<u8>>,
+    output: Box<dyn Write>,
+    /// Bytes taken from the input that have not been read by the program yet
+    received: VecDeque<u8>,
+    ended: bool,
+    control: u16,
+}
+
+impl Uart {
+    pub fn new(mut input: Box<dyn Read + Send>, output: Box<dyn Write>) -> Uart {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut buffer: [u8; READ_SIZE] = [0; READ_SIZE];
+            loop {
+                match input.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(count) => {
+                        // the UART has been dropped
+                        if sender.send(buffer[..count].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                    Err(error) if error.kind() == std::io::ErrorKind::Interrupted => (),
+                    Err(error) => {
+                        error!("UART could not receive: {}", error);
+                        break;
+                    }
+                }
+            }
+        });
+        Uart::with_receiver(receiver, output)
+    }
+
+    fn with_receiver(input: Receiver<Vec<u8>>, output: Box<dyn Write>) -> Uart {
+        Uart {
+            input,
+            output,
+            received: VecDeque::new(),
+            ended: false,
+            control: 0,
+        }
+    }
+
+    /// A UART receiving from stdin and transmitting to stdout
+    pub fn stdio() -> Uart {
+        Uart::new(Box::new(std::io::stdin()), Box::new(std::io::stdout()))
+    }
+
+    /// A UART that receives a fixed sequence of bytes, then reports the end of input
+    pub fn scripted(input: &[u8], output: Box<dyn Write>) -> Uart {
+        // everything is received up front so programs see the same input every run
+        let (sender, receiver) = mpsc::channel();
+        if !input.is_empty() {
+            sender.send(input.to_vec()).unwrap();
+        }
+        Uart::with_receiver(receiver, output)
+    }
+
+    /// Take any bytes the input thread has read since last time, without waiting for more
+    fn receive(&mut self) {
+        while !self.ended {
+            match self.input.try_recv() {
+                Ok(bytes) => self.received.extend(bytes),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => self.ended = true,
+            }
+        }
+    }
+
+    fn status(&self) -> u16 {
+        let mut status: u16 = TX_READY;
+        if !self.received.is_empty() {
+            status |= RX_AVAILABLE;
+        } else if self.ended {
+            status |= RX_ENDED;
+        }
+        status
+    }
+}
+
+impl Device for Uart {
+    fn name(&self) -> &str {
+        "UART"
+    }
+
+    fn size(&self) -> usize {
+        3
+    }
+
+    fn read(&mut self, offset: u16) -> u16 {
+        self.receive();
+        match offset {
+            UART_DATA => self.received.pop_front().map_or(0, |x| x as u16),
+            UART_STATUS => self.status(),
+            _ => self.control,
+        }
+    }
+
+    fn peek(&self, offset: u16) -> u16 {
+        match offset {
+            UART_DATA => self.received.front().map_or(0, |x| *x as u16),
+            UART_STATUS => self.status(),
+            _ => self.control,
+        }
+    }
+
+    fn write(&mut self, offset: u16, value: u16) {
+        match offset {
+            UART_DATA => (),
+            UART_STATUS => {
+                warn!("Ignored write of {:#06X} to UART status register", value);
+                return;
+            }
+            _ => {
+                self.control = value;
+                return;
+            }
+        }
+        // flushed straight away so output appears as the program runs
+        let sent = self
+            .output
+            .write_all(&[value as u8])
+            .and_then(|_| self.output.flush());
+        if let Err(error) = sent {
+            error!("UART could not transmit: {}", error);
+        }
+    }
+
+    fn interrupt(&mut self) -> bool {
+        if self.control & RX_INTERRUPT == 0 {
+            return false;
+        }
+        self.receive();
+        self.status() & (RX_AVAILABLE | RX_ENDED) != 0
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::coredump::{parse_word, CoreDump};
+use crate::disassembler::disassemble;
+use crate::error::SimError;
+
+/// Register and memory values a dump is expected to hold, covering all or part of the machine
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expectations {
+    pub registers: BTreeMap<u8, u16>,
+    pub memory: BTreeMap<u16, u16>,
+}
+
+impl Expectations {
+    /// Expect every register and word of memory to match a dump
+    pub fn from_dump(dump: &CoreDump) -> Expectations {
+        Expectations {
+            registers: (0..16).zip(dump.registers).collect(),
+            memory: (0..=u16::MAX).zip(dump.memory.iter().copied()).collect(),
+        }
+    }
+
+    /// Parse an expectations file, made of the register (`R0x5: 0x1A13`) and memory
+    /// (`M0x0011: 0x00F5` or `M0x0020-0x002F: 0x0000`) lines of a core dump, comments start with `#`
+    pub fn parse(text: &str) -> Result<Expectations, SimError> {
+        let mut expectations = Expectations {
+            registers: BTreeMap::new(),
+            memory: BTreeMap::new(),
+        };
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: &str| SimError::parse(i + 1, message);
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| error("expected 'name: value'"))?;
+            let value = parse_word(value.trim()).ok_or_else(|| error("invalid word"))?;
+            match (name.strip_prefix('R'), name.strip_prefix('M')) {
+                (Some(register), _) => match parse_word(register) {
+                    Some(register) if register < 16 => {
+                        expectations.registers.insert(register as u8, value);
+                    }
+                    _ => return Err(error("invalid register")),
+                },
+                (_, Some(range)) => {
+                    let (start, end) = match range.split_once('-') {
+                        Some((start, end)) => (parse_word(start), parse_word(end)),
+                        None => (parse_word(range), parse_word(range)),
+                    };
+                    match (start, end) {
+                        (Some(start), Some(end)) if start <= end => {
+                            for address in start..=end {
+                                expectations.memory.insert(address, value);
+                            }
+                        }
+                        _ => return Err(error("invalid address")),
+                    }
+                }
+                _ => return Err(error(&format!("unexpected line '{}'", line))),
+            }
+        }
+        Ok(expectations)
+    }
+
+    /// Read either a core dump or an expectations file
+    pub fn load(path: &str) -> Result<Expectations, SimError> {
+        let text = std::fs::read_to_string(path)?;
+        match text.starts_with("Core dump") {
+            true => Ok(Expectations::from_dump(&CoreDump::parse(&text)?)),
+            false => Expectations::parse(&text),
+        }
+    }
+}
+
+/// Where a difference was found
+#[derive(Debug, Clone, PartialEq)]
+pub enum Location {
+    Register(u8),
+    Memory(u16),
+}
+
+/// A register or word of memory that does not hold the expected value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub location: Location,
+    pub expected: u16,
+    pub actual: u16,
+}
+
+/// Find every expected value that a dump does not match, registers first then memory by address
+pub fn compare(expected: &Expectations, actual: &CoreDump) -> Vec<Difference> {
+    let registers = expected.registers.iter().map(|(register, value)| {
+        (
+            Location::Register(*register),
+            *value,
+            actual.registers[*register as usize],
+        )
+    });
+    let memory = expected.memory.iter().map(|(address, value)| {
+        (
+            Location::Memory(*address),
+            *value,
+            actual.memory[*address as usize],
+        )
+    });
+    registers
+        .chain(memory)
+        .filter(|(_, expected, actual)| expected != actual)
+        .map(|(location, expected, actual)| Difference {
+            location,
+            expected,
+            actual,
+        })
+        .collect()
+}
+
+/// Lay differences out as a table, memory in the first code_length words, where the program
+/// was loaded, is disassembled
+pub fn format_differences(differences: &[Difference], code_length: usize) -> String {
+    let mut table = format!("{:<10}{:<10}{}\n", "Location", "Expected", "Actual");
+    for difference in differences {
+        let location: String = match difference.location {
+            Location::Register(register) => format!("R{}", register),
+            Location::Memory(address) => format!("M{:#06X}", address),
+        };
+        let mut row = format!(
+            "{:<10}{:<10}{:<10}",
+            location,
+            format!("{:#06X}", difference.expected),
+            format!("{:#06X}", difference.actual)
+        );
+        if let Location::Memory(address) = difference.location {
+            if (address as usize) < code_length {
+                write!(
+                    row,
+                    "{} -> {}",
+                    disassemble(difference.expected),
+                    disassemble(difference.actual)
+                )
+                .unwrap();
+            }
+        }
+        writeln!(table, "{}", row.trim_end()).unwrap();
+    }
+    table
+}
@@ -0,0 +1,67 @@
+use crate::processor::Processor;
+use crate::types::{InstructionToken, InstructionType, Opcode, SpecialOperation};
+
+/// Disassemble a word of memory into the mnemonic form accepted by the assembler,
+/// words that are not valid instructions are shown as `.word` directives
+pub fn disassemble(word: u16) -> String {
+    let token: InstructionToken = Processor::decode_instruction(word);
+    let mnemonic = token.opcode.mnemonic();
+    let byte_2: u8 = token.nibble_3 << 4 | token.nibble_4;
+    match (&token.instruction_type, &token.opcode) {
+        (_, Opcode::LoadWord) | (_, Opcode::SaveWord) => format!(
+            "{} r{}, {}(r{})",
+            mnemonic, token.nibble_3, token.nibble_2, token.nibble_4
+        ),
+        (_, Opcode::SetPcIf) => format!(
+            "{} {:#X}, r{}, r{}",
+            mnemonic, token.nibble_2, token.nibble_3, token.nibble_4
+        ),
+        (InstructionType::Register, _) => format!(
+            "{} r{}, r{}, r{}",
+            mnemonic, token.nibble_2, token.nibble_3, token.nibble_4
+        ),
+        (InstructionType::Set, _) => format!("{} r{}, {:#04X}", mnemonic, token.nibble_2, byte_2),
+        (_, Opcode::Special) if word == 0xF100 => String::from("halt"),
+        (_, Opcode::Special) if token.nibble_2 == 1 => format!("halt {:#04X}", byte_2),
+        (_, Opcode::Special) if token.nibble_4 == 0 => {
+            match SpecialOperation::from_u8(token.nibble_2) {
+                Some(operation) if operation.has_register() => {
+                    format!("{} r{}", operation.mnemonic(), token.nibble_3)
+                }
+                Some(operation) if token.nibble_3 == 0 => operation.mnemonic().to_string(),
+                _ => format!(".word {:#06X}", word),
+            }
+        }
+        _ => format!(".word {:#06X}", word),
+    }
+}
+
+/// Disassemble a region of memory starting at address 0 into a listing of
+/// `address: word  instruction` lines
+///
+/// Trailing zero words are dropped and runs of 4 or more zero words are collapsed into one line
+pub fn disassemble_memory(memory: &[u16]) -> Vec<String> {
+    let length = memory.iter().rposition(|x| *x != 0).map_or(0, |x| x + 1);
+    let mut listing: Vec<String> = Vec::new();
+    let mut address: usize = 0;
+    while address < length {
+        let zero_run = memory[address..length]
+            .iter()
+            .take_while(|x| **x == 0)
+            .count();
+        if zero_run >= 4 {
+            listing.push(format!("...     {} zero words", zero_run));
+            address += zero_run;
+            continue;
+        }
+        let word = memory[address];
+        listing.push(format!(
+            "{:#06X}: {:#06X}  {}",
+            address,
+            word,
+            disassemble(word)
+        ));
+        address += 1;
+    }
+    listing
+}
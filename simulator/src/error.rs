@@ -0,0 +1,90 @@
+use std::fmt;
+
+/// An error that stops a program being loaded or run
+#[derive(Debug)]
+pub enum SimError {
+    /// A file could not be read or written
+    Io(std::io::Error),
+    /// Text could not be parsed, at a 1 indexed line and column
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// An instruction with an opcode the processor does not implement was decoded
+    InvalidOpcode { pc: u16, instruction: u16 },
+    /// A special instruction the processor does not implement was executed
+    InvalidSpecial { pc: u16, instruction: u16 },
+    /// A program or memory image does not fit in the 65536 words of memory
+    ProgramTooLarge { words: usize },
+    /// A run reached its clock cycle limit before it finished
+    Watchdog { cycles: u64 },
+    /// A device could not be mapped as it overlaps another or does not fit in memory
+    DeviceMapping {
+        name: String,
+        start: u16,
+        size: usize,
+    },
+}
+
+impl SimError {
+    /// A parse error that applies to a whole line
+    pub fn parse(line: usize, message: impl Into<String>) -> SimError {
+        SimError::Parse {
+            line,
+            column: 1,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::Io(error) => write!(f, "{}", error),
+            SimError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            SimError::InvalidOpcode { pc, instruction } => write!(
+                f,
+                "invalid opcode in instruction {:#06X} at {:#06X}",
+                instruction, pc
+            ),
+            SimError::InvalidSpecial { pc, instruction } => write!(
+                f,
+                "unimplemented special instruction {:#06X} at {:#06X}",
+                instruction, pc
+            ),
+            SimError::ProgramTooLarge { words } => write!(
+                f,
+                "program is {} words, larger than the 65536 words of memory",
+                words
+            ),
+            SimError::Watchdog { cycles } => {
+                write!(f, "watchdog expired after {} clock cycles", cycles)
+            }
+            SimError::DeviceMapping { name, start, size } => write!(
+                f,
+                "{} of {} words at {:#06X} overlaps another device or does not fit in memory",
+                name, size, start
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SimError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SimError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SimError {
+    fn from(error: std::io::Error) -> SimError {
+        SimError::Io(error)
+    }
+}
@@ -0,0 +1,158 @@
+use std::path::Path;
+
+use crate::error::SimError;
+
+/// Bytes every PNG file starts with
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+/// Largest block of data a stored deflate block can hold
+const STORED_BLOCK_SIZE: usize = 0xFFFF;
+
+/// How a frame is written to a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    /// Binary portable pixmap
+    Ppm,
+    Png,
+}
+
+impl ImageFormat {
+    /// The format for an image file, PNG for a .png extension and PPM for anything else
+    pub fn from_path(path: &str) -> ImageFormat {
+        match path.to_lowercase().ends_with(".png") {
+            true => ImageFormat::Png,
+            false => ImageFormat::Ppm,
+        }
+    }
+}
+
+/// An image shown by a display device, one RGB565 colour word per pixel in rows from the top
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u16>,
+}
+
+impl Frame {
+    /// The colour of a pixel
+    pub fn pixel(&self, x: usize, y: usize) -> u16 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Every pixel as 8 bit red, green and blue bytes
+    fn rgb(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|x| rgb565_to_rgb(*x)).collect()
+    }
+
+    /// Encode as a binary PPM
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        bytes.extend(self.rgb());
+        bytes
+    }
+
+    /// Encode as an uncompressed PNG
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut header: Vec<u8> = Vec::new();
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, default compression, filtering and no interlacing
+        header.extend([8, 2, 0, 0, 0]);
+        // each row starts with it's filter type, 0 for none
+        let rgb: Vec<u8> = self.rgb();
+        let mut rows: Vec<u8> = Vec::new();
+        for row in rgb.chunks(self.width * 3) {
+            rows.push(0);
+            rows.extend(row);
+        }
+        let mut bytes: Vec<u8> = PNG_SIGNATURE.to_vec();
+        write_chunk(&mut bytes, b"IHDR", &header);
+        write_chunk(&mut bytes, b"IDAT", &zlib_stored(&rows));
+        write_chunk(&mut bytes, b"IEND", &[]);
+        bytes
+    }
+
+    /// Write to a file, in the format given by it's extension
+    pub fn save(&self, path: &str) -> Result<(), SimError> {
+        let bytes: Vec<u8> = match ImageFormat::from_path(path) {
+            ImageFormat::Ppm => self.to_ppm(),
+            ImageFormat::Png => self.to_png(),
+        };
+        Ok(std::fs::write(path, bytes)?)
+    }
+}
+
+/// A path with a number added to the end of the file name, before the extension
+pub fn numbered_path(path: &str, number: u32) -> String {
+    let path: &Path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name: String = match path.extension() {
+        Some(extension) => format!("{}-{:04}.{}", stem, number, extension.to_string_lossy()),
+        None => format!("{}-{:04}", stem, number),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Expand a 5 bit red, 6 bit green and 5 bit blue colour to 8 bits per channel
+pub fn rgb565_to_rgb(colour: u16) -> [u8; 3] {
+    let red: u8 = (colour >> 11) as u8 & 0x1F;
+    let green: u8 = (colour >> 5) as u8 & 0x3F;
+    let blue: u8 = colour as u8 & 0x1F;
+    [
+        red << 3 | red >> 2,
+        green << 2 | green >> 4,
+        blue << 3 | blue >> 2,
+    ]
+}
+
+/// Append a PNG chunk, it's length, type, data and CRC of the type and data
+fn write_chunk(bytes: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    bytes.extend((data.len() as u32).to_be_bytes());
+    let start: usize = bytes.len();
+    bytes.extend(kind);
+    bytes.extend(data);
+    let crc: u32 = crc32(&bytes[start..]);
+    bytes.extend(crc.to_be_bytes());
+}
+
+/// Wrap data in a zlib stream of stored deflate blocks, leaving it uncompressed
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // deflate with a 32K window and no preset dictionary
+    let mut bytes: Vec<u8> = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = match data.is_empty() {
+        true => vec![&[]],
+        false => data.chunks(STORED_BLOCK_SIZE).collect(),
+    };
+    for (i, block) in blocks.iter().enumerate() {
+        let length: u16 = block.len() as u16;
+        bytes.push((i + 1 == blocks.len()) as u8);
+        bytes.extend(length.to_le_bytes());
+        bytes.extend((!length).to_le_bytes());
+        bytes.extend(*block);
+    }
+    bytes.extend(adler32(data).to_be_bytes());
+    bytes
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFFFFFF;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => crc >> 1 ^ 0xEDB88320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b): (u32, u32) = (1, 0);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
@@ -0,0 +1,310 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use log::{debug, error, info, warn};
+
+use crate::processor::Processor;
+use crate::types::{Breakpoint, RunState, StopCause};
+
+/// Largest packet a client may send, in bytes
+const PACKET_SIZE: usize = 0x4000;
+/// Byte a client sends outside of a packet to interrupt a running program
+const INTERRUPT: u8 = 0x03;
+/// Instructions run between checks for an interrupt while continuing
+const INTERRUPT_CHECK_INSTRUCTIONS: u64 = 1000;
+/// Signal reported when the program stops at a breakpoint or after a step
+const SIGTRAP: u8 = 5;
+/// Signal reported when the client interrupts the program
+const SIGINT: u8 = 2;
+
+/// A connection to a client that can be checked for an interrupt while a program runs
+pub trait Connection: Read + Write {
+    /// Whether the client has sent an interrupt, without waiting if it has sent nothing
+    fn interrupted(&mut self) -> std::io::Result<bool>;
+}
+
+impl Connection for TcpStream {
+    fn interrupted(&mut self) -> std::io::Result<bool> {
+        self.set_nonblocking(true)?;
+        let mut byte: [u8; 1] = [0];
+        let read = self.read(&mut byte);
+        self.set_nonblocking(false)?;
+        match read {
+            // the client has gone, stop so the closed connection is noticed
+            Ok(0) => Ok(true),
+            Ok(_) => Ok(byte[0] == INTERRUPT),
+            Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Serves a processor to a client of the GDB remote serial protocol
+///
+/// Memory is word addressed so addresses match the PC held in R1, each word (and register)
+/// is sent as 2 little endian bytes
+pub struct GdbStub {
+    processor: Processor,
+    exit_code: Option<u8>,
+    /// Signal the program last stopped with
+    signal: u8,
+}
+
+impl GdbStub {
+    pub fn new(processor: Processor) -> GdbStub {
+        GdbStub {
+            processor,
+            exit_code: None,
+            signal: SIGTRAP,
+        }
+    }
+
+    /// The processor being served
+    pub fn processor(&self) -> &Processor {
+        &self.processor
+    }
+
+    /// Wait for a client to connect on a TCP address such as `127.0.0.1:1234`, then serve it
+    pub fn listen(&mut self, address: &str) -> std::io::Result<()> {
+        let listener = TcpListener::bind(address)?;
+        info!("Waiting for GDB to connect on {}", listener.local_addr()?);
+        let (stream, client) = listener.accept()?;
+        info!("GDB connected from {}", client);
+        self.serve(stream)
+    }
+
+    /// Answer packets from a connected client until it detaches, kills the program or disconnects
+    pub fn serve(&mut self, mut stream: impl Connection) -> std::io::Result<()> {
+        while let Some(packet) = read_packet(&mut stream)? {
+            debug!("GDB packet: {}", packet);
+            let reply: Option<String> = self.handle_packet(&packet, &mut stream);
+            write_packet(&mut stream, reply.as_deref().unwrap_or("OK"))?;
+            if reply.is_none() {
+                info!("GDB detached");
+                return Ok(());
+            }
+        }
+        info!("GDB disconnected");
+        Ok(())
+    }
+
+    /// Reply to a packet, returns None when the session should end after replying OK
+    fn handle_packet(&mut self, packet: &str, stream: &mut impl Connection) -> Option<String> {
+        // the first character may take more than a byte if the client sent invalid UTF-8
+        let (command, arguments) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+        let reply: String = match command {
+            "?" => self.stop_reply(),
+            "g" => self.processor.registers().iter().map(word_to_hex).collect(),
+            "G" => match hex_to_words(arguments) {
+                Some(words) if words.len() == 16 => {
+                    for (register, value) in words.into_iter().enumerate() {
+                        self.processor.set_register(register, value);
+                    }
+                    String::from("OK")
+                }
+                _ => String::from("E01"),
+            },
+            "p" => match usize::from_str_radix(arguments, 16) {
+                Ok(register) if register < 16 => word_to_hex(&self.processor.registers()[register]),
+                _ => String::from("E01"),
+            },
+            "P" => match arguments.split_once('=').and_then(|(register, value)| {
+                Some((
+                    usize::from_str_radix(register, 16).ok()?,
+                    hex_to_words(value)?,
+                ))
+            }) {
+                Some((register, value)) if register < 16 && value.len() == 1 => {
+                    self.processor.set_register(register, value[0]);
+                    String::from("OK")
+                }
+                _ => String::from("E01"),
+            },
+            "m" => match parse_address_length(arguments) {
+                Some((address, length)) => {
+                    let words: usize = length.div_ceil(2);
+                    let hex: String = (0..words)
+                        .map(|i| word_to_hex(&self.processor.peek(address.wrapping_add(i as u16))))
+                        .collect();
+                    hex[..length * 2].to_string()
+                }
+                None => String::from("E01"),
+            },
+            "M" => match arguments
+                .split_once(':')
+                .and_then(|(range, data)| Some((parse_address_length(range)?, hex_to_words(data)?)))
+            {
+                Some(((address, length), words)) if length == words.len() * 2 => {
+                    for (i, word) in words.into_iter().enumerate() {
+                        self.processor
+                            .set_memory(address.wrapping_add(i as u16), word);
+                    }
+                    String::from("OK")
+                }
+                _ => String::from("E01"),
+            },
+            "s" => self.resume(false, stream),
+            "c" => self.resume(true, stream),
+            "Z" | "z" => match arguments.split(',').collect::<Vec<&str>>()[..] {
+                ["0", address, _] => match u16::from_str_radix(address, 16) {
+                    Ok(address) => {
+                        let breakpoint = Breakpoint::Address(address);
+                        match command {
+                            "Z" => self.processor.add_breakpoint(breakpoint),
+                            _ => {
+                                self.processor.remove_breakpoint(&breakpoint);
+                            }
+                        }
+                        String::from("OK")
+                    }
+                    Err(_) => String::from("E01"),
+                },
+                _ => String::new(),
+            },
+            "H" | "T" => String::from("OK"),
+            "D" | "k" => return None,
+            _ => match packet {
+                x if x.starts_with("qSupported") => format!("PacketSize={:x}", PACKET_SIZE),
+                "qAttached" => String::from("1"),
+                // unsupported packets get an empty reply
+                _ => String::new(),
+            },
+        };
+        Some(reply)
+    }
+
+    /// Run a single instruction or until a breakpoint or the client interrupts, then report
+    /// why the processor stopped
+    fn resume(&mut self, until_breakpoint: bool, stream: &mut impl Connection) -> String {
+        self.signal = SIGTRAP;
+        let mut instructions: u64 = 0;
+        while self.exit_code.is_none() {
+            instructions += 1;
+            if until_breakpoint && instructions.is_multiple_of(INTERRUPT_CHECK_INSTRUCTIONS) {
+                let interrupted: bool = stream.interrupted().unwrap_or_else(|error| {
+                    warn!("Could not check for an interrupt from GDB: {}", error);
+                    true
+                });
+                if interrupted {
+                    info!("GDB interrupted the program");
+                    self.signal = SIGINT;
+                    break;
+                }
+            }
+            let running: RunState = match self.processor.step_instruction() {
+                Ok(running) => running,
+                Err(error) => {
+                    error!("Program stopped with an error: {}", error);
+                    self.exit_code = Some(1);
+                    break;
+                }
+            };
+            if self.processor.hit_breakpoint().is_some()
+                || self.processor.watchpoint_hit().is_some()
+            {
+                break;
+            }
+            if let RunState::Stop(reason) = running {
+                match reason.cause {
+                    StopCause::Halt(_) | StopCause::Terminated => {
+                        self.exit_code = Some(reason.exit_code())
+                    }
+                    // the program hasn't finished, so the client can carry on with it
+                    _ => {
+                        info!("Program stopped: {}", reason);
+                        break;
+                    }
+                }
+            }
+            if !until_breakpoint {
+                break;
+            }
+        }
+        self.stop_reply()
+    }
+
+    /// Exited with the program's exit code once it has finished, otherwise stopped by the
+    /// signal it last stopped with
+    fn stop_reply(&self) -> String {
+        match self.exit_code {
+            Some(exit_code) => format!("W{:02x}", exit_code),
+            None => format!("S{:02x}", self.signal),
+        }
+    }
+}
+
+/// Read the next packet, acknowledging it, or None if the connection closed
+fn read_packet(stream: &mut (impl Read + Write)) -> std::io::Result<Option<String>> {
+    loop {
+        // skip acknowledgements until the start of a packet, an interrupt arriving once the
+        // program has stopped has nothing left to interrupt
+        match read_byte(stream)? {
+            Some(b'$') => (),
+            Some(_) => continue,
+            None => return Ok(None),
+        }
+        let mut data: Vec<u8> = Vec::new();
+        loop {
+            match read_byte(stream)? {
+                Some(b'#') => break,
+                Some(byte) => data.push(byte),
+                None => return Ok(None),
+            }
+        }
+        let mut checksum: [u8; 2] = [0; 2];
+        stream.read_exact(&mut checksum)?;
+        let expected = u8::from_str_radix(&String::from_utf8_lossy(&checksum), 16).ok();
+        let actual: u8 = data.iter().fold(0, |sum, x| sum.wrapping_add(*x));
+        if expected != Some(actual) {
+            warn!("GDB packet had a bad checksum, requesting retransmission");
+            stream.write_all(b"-")?;
+            continue;
+        }
+        stream.write_all(b"+")?;
+        return Ok(Some(String::from_utf8_lossy(&data).to_string()));
+    }
+}
+
+fn read_byte(stream: &mut impl Read) -> std::io::Result<Option<u8>> {
+    let mut byte: [u8; 1] = [0];
+    match stream.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+fn write_packet(stream: &mut impl Write, data: &str) -> std::io::Result<()> {
+    let checksum: u8 = data.bytes().fold(0, |sum, x| sum.wrapping_add(x));
+    write!(stream, "${}#{:02x}", data, checksum)?;
+    stream.flush()
+}
+
+/// Parse the `address,length` argument of memory packets, None if the address is outside
+/// memory or the length wouldn't fit in a packet
+fn parse_address_length(text: &str) -> Option<(u16, usize)> {
+    let (address, length) = text.split_once(',')?;
+    let length: usize = usize::from_str_radix(length, 16).ok()?;
+    // the hex of a read is twice the length
+    if length > PACKET_SIZE / 2 {
+        return None;
+    }
+    Some((u16::from_str_radix(address, 16).ok()?, length))
+}
+
+fn word_to_hex(word: &u16) -> String {
+    format!("{:02x}{:02x}", word & 0xFF, word >> 8)
+}
+
+fn hex_to_words(hex: &str) -> Option<Vec<u16>> {
+    if !hex.len().is_multiple_of(4) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(4)
+        .map(|i| {
+            let low = u16::from_str_radix(&hex[i..i + 2], 16).ok()?;
+            let high = u16::from_str_radix(&hex[i + 2..i + 4], 16).ok()?;
+            Some(high << 8 | low)
+        })
+        .collect()
+}
@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+
+use crate::statemachine::StateMachine;
+use crate::trace::TraceRecord;
+use crate::types::{ControlSignals, InstructionToken, PipelineRegisters};
+
+/// Everything a clock cycle can change, as it was before the cycle ran
+pub struct UndoEntry {
+    pub clock_cycle: u64,
+    pub registers: [u16; 16],
+    /// Address and previous value of every memory write, in the order they happened
+    pub memory_writes: Vec<(u16, u16)>,
+    pub instruction_register: u16,
+    pub instruction_address: u16,
+    pub instruction_token: InstructionToken,
+    pub control_signals: ControlSignals,
+    pub state_machine: StateMachine,
+    pub pipeline_registers: PipelineRegisters,
+    pub epc: u16,
+    pub interrupts_enabled: bool,
+    pub fetch_cycle: Option<u64>,
+    pub traced: Option<TraceRecord>,
+}
+
+/// Undo entries for the most recent clock cycles, the oldest are dropped once the limit is reached
+pub struct History {
+    entries: VecDeque<UndoEntry>,
+    limit: usize,
+}
+
+impl History {
+    pub fn new(limit: usize) -> History {
+        History {
+            entries: VecDeque::new(),
+            limit,
+        }
+    }
+
+    /// Maximum number of clock cycles that can be undone, 0 disables recording
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.entries.len() > limit {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Number of clock cycles that can currently be undone
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn push(&mut self, entry: UndoEntry) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.entries.len() == self.limit {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Forget every entry
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Take the entry for the most recent clock cycle
+    pub fn pop(&mut self) -> Option<UndoEntry> {
+        self.entries.pop_back()
+    }
+}
@@ -1,16 +1,15 @@
 use log::debug;
 
-pub fn parse_instruction(instruction: &str) -> Option<u16> {
+/// Parse an instruction written as hex digits, returns None for an empty or comment only line
+pub fn parse_instruction(instruction: &str) -> Result<Option<u16>, String> {
     let instruction_cleaned: String = instruction.replace(' ', "");
-    let instruction_string = instruction_cleaned
-        .split('#')
-        .next()
-        .expect("Couldn't remove comment");
+    let instruction_string = instruction_cleaned.split('#').next().unwrap_or("");
     if instruction_string.is_empty() {
         debug!("Read empty line");
-        return None;
+        return Ok(None);
     }
-    let instruction: u16 = u16::from_str_radix(instruction_string, 16).expect("Invalid hex number");
+    let instruction: u16 = u16::from_str_radix(instruction_string, 16)
+        .map_err(|_| format!("invalid hex instruction '{}'", instruction_string))?;
     debug!("Read instruction: {:#06X}", instruction);
-    Some(instruction)
+    Ok(Some(instruction))
 }
@@ -1,6 +1,24 @@
 mod alu;
 pub mod args;
+pub mod assembler;
+pub mod bus;
+pub mod console;
+pub mod coredump;
+pub mod debugger;
+pub mod devices;
+pub mod diff;
+pub mod disassembler;
+pub mod error;
+pub mod frame;
+pub mod gdbstub;
+mod history;
 mod instructions;
 pub mod processor;
+pub mod profiler;
+pub mod reference;
+pub mod snapshot;
 mod statemachine;
+pub mod stats;
+pub mod trace;
 pub mod types;
+pub mod vcd;
@@ -1,19 +1,54 @@
+use std::collections::HashMap;
 use std::fs::File;
 
-use log::info;
+use log::{error, info};
 use simplelog::{
     ColorChoice, CombinedLogger, ConfigBuilder, LevelFilter, LevelPadding, TargetPadding,
     TermLogger, TerminalMode, WriteLogger,
 };
 
-use simulator::{args, processor::Processor, types::RunState};
+use simulator::{
+    args::{self, ArgsError},
+    assembler::{assemble, assemble_program},
+    coredump::CoreDump,
+    debugger::Debugger,
+    devices::{Framebuffer, Timer, Uart, FRAMEBUFFER_BASE, TIMER_BASE, UART_BASE},
+    diff::{compare, format_differences, Expectations},
+    disassembler::disassemble_memory,
+    error::SimError,
+    gdbstub::GdbStub,
+    processor::Processor,
+    profiler::Profile,
+    trace::Trace,
+    types::{ExecutionMode, RunState},
+    vcd::VcdWriter,
+};
+
+/// Number of instructions listed in the profile's hotspot table
+const HOTSPOTS: usize = 20;
 
 fn main() {
-    let args = args::parse_args();
-    if args.is_none() {
+    let args = match args::parse_args() {
+        Ok(args) => args,
+        Err(ArgsError::Help) => return,
+        // scripts rely on diff's exit status, so a usage error can't look like a match
+        Err(ArgsError::Usage) => std::process::exit(2),
+    };
+    if let Some((expected, actual)) = &args.diff {
+        std::process::exit(diff_dumps(expected, actual, args.diff_program.as_deref()));
+    }
+    if args.disassemble {
+        match read_memory_image(&args.path_to_file) {
+            Ok(memory) => disassemble_memory(&memory)
+                .iter()
+                .for_each(|line| println!("{}", line)),
+            Err(error) => {
+                eprintln!("Could not read {}: {}", args.path_to_file, error);
+                std::process::exit(1);
+            }
+        }
         return;
     }
-    let args = args.unwrap();
     println!("------------------------------------------------------------------------");
     let config = ConfigBuilder::new()
         .set_level_padding(LevelPadding::Right)
@@ -36,12 +71,186 @@ fn main() {
         ),
     ])
     .unwrap();
+    let execution_mode: ExecutionMode = match args.functional {
+        true => ExecutionMode::Functional,
+        false => ExecutionMode::Cycle,
+    };
     let mut processor: Processor =
-        Processor::new_from_file(args.path_to_file, args.breakpoint, true);
-    let mut running: RunState = RunState::Continue;
+        match Processor::new_from_file(args.path_to_file.clone(), args.breakpoint, true) {
+            Ok(processor) => processor.with_execution_mode(execution_mode),
+            Err(error) => {
+                error!("Could not load {}: {}", args.path_to_file, error);
+                std::process::exit(1);
+            }
+        };
+    if args.uart {
+        processor = processor
+            .with_device(UART_BASE, Box::new(Uart::stdio()))
+            .unwrap_or_else(|error| {
+                error!("Could not map UART: {}", error);
+                std::process::exit(1);
+            });
+    }
+    if args.timer {
+        processor = processor
+            .with_device(TIMER_BASE, Box::new(Timer::new()))
+            .unwrap_or_else(|error| {
+                error!("Could not map timer: {}", error);
+                std::process::exit(1);
+            });
+    }
+    if let Some(path) = &args.framebuffer {
+        processor = processor
+            .with_device(FRAMEBUFFER_BASE, Box::new(Framebuffer::default()))
+            .unwrap_or_else(|error| {
+                error!("Could not map framebuffer: {}", error);
+                std::process::exit(1);
+            });
+        processor.set_frame_path(Some(path.clone()));
+    }
+    for breakpoint in args.break_at {
+        processor.add_breakpoint(breakpoint);
+    }
+    for watchpoint in args.watch {
+        processor.add_watchpoint(watchpoint);
+    }
+    processor.set_history_limit(args.history);
+    processor.set_compact_dump(args.compact_dump);
+    if let Some(path) = &args.trace {
+        match Trace::create(path) {
+            Ok(trace) => processor.set_trace(Some(trace)),
+            Err(error) => {
+                error!("Could not create trace {}: {}", path, error);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(path) = &args.vcd {
+        match VcdWriter::create(path) {
+            Ok(vcd) => processor.set_vcd(Some(vcd)),
+            Err(error) => {
+                error!("Could not create VCD file {}: {}", path, error);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(address) = args.gdb {
+        if let Err(error) = GdbStub::new(processor).listen(&address) {
+            error!("Could not serve GDB on {}: {}", address, error);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.interactive {
+        let mut debugger: Debugger = Debugger::new(processor);
+        if let Err(error) = debugger.run(std::io::stdin().lock(), std::io::stdout()) {
+            error!("Could not read commands: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let mut running: Result<RunState, SimError> = Ok(RunState::Continue);
     info!("Beginning execution");
-    while running == RunState::Continue {
+    while let Ok(RunState::Continue) = running {
         running = processor.run();
     }
-    info!("Execution complete");
+    // exiting skips destructors, so buffered trace lines have to be written out first
+    if let Some(mut trace) = processor.take_trace() {
+        if let Err(error) = trace.flush() {
+            error!("Could not write trace: {}", error);
+        }
+    }
+    if let Some(mut vcd) = processor.take_vcd() {
+        if let Err(error) = vcd.flush() {
+            error!("Could not write VCD file: {}", error);
+        }
+    }
+    if args.stats {
+        println!("------------------------------------------------------------------------");
+        print!("{}", processor.stats());
+    }
+    if args.profile {
+        print_profile(&processor, &read_labels(&args.path_to_file));
+    }
+    match running {
+        Ok(RunState::Stop(reason)) => {
+            info!("Execution complete");
+            info!("Processor {}", reason);
+            std::process::exit(reason.exit_code() as i32);
+        }
+        Ok(RunState::Continue) => info!("Execution complete"),
+        Err(error) => {
+            error!("Execution failed: {}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Print the hotspots, labelled regions and annotated disassembly of a finished run
+fn print_profile(processor: &Processor, labels: &HashMap<String, u16>) {
+    let profile = Profile::new(processor.stats(), processor.memory(), labels);
+    println!("------------------------------------------------------------------------");
+    print!("{}", profile.format_hotspots(HOTSPOTS));
+    if !labels.is_empty() {
+        println!();
+        print!("{}", profile.format_regions());
+    }
+    println!();
+    print!("{}", profile.annotate());
+}
+
+/// Labels defined by a .ayu program, none if it can't be assembled
+fn read_labels(path_to_file: &str) -> HashMap<String, u16> {
+    std::fs::read_to_string(path_to_file)
+        .ok()
+        .and_then(|x| assemble_program(&x).ok())
+        .map(|x| x.labels)
+        .unwrap_or_default()
+}
+
+/// Read the memory contents held in either a .ayu program or a core.dump
+fn read_memory_image(path_to_file: &str) -> Result<Vec<u16>, SimError> {
+    let contents: String = std::fs::read_to_string(path_to_file)?;
+    match contents.starts_with("Core dump") {
+        true => Ok(CoreDump::parse(&contents)?.memory),
+        false => assemble(&contents),
+    }
+}
+
+/// Number of words a .ayu program assembles to
+fn program_length(path_to_file: &str) -> Result<usize, SimError> {
+    Ok(assemble(&std::fs::read_to_string(path_to_file)?)?.len())
+}
+
+/// Print the differences between two dumps, returning the exit status. Differences in the
+/// code of the program, if there is one, are disassembled
+fn diff_dumps(expected: &str, actual: &str, program: Option<&str>) -> i32 {
+    let expectations = match Expectations::load(expected) {
+        Ok(expectations) => expectations,
+        Err(error) => {
+            eprintln!("Could not read {}: {}", expected, error);
+            return 2;
+        }
+    };
+    let actual = match CoreDump::load(actual) {
+        Ok(dump) => dump,
+        Err(error) => {
+            eprintln!("Could not read {}: {}", actual, error);
+            return 2;
+        }
+    };
+    let code_length: usize = match program.map(program_length).transpose() {
+        Ok(length) => length.unwrap_or(0),
+        Err(error) => {
+            eprintln!("Could not read {}: {}", program.unwrap_or_default(), error);
+            return 2;
+        }
+    };
+    let differences = compare(&expectations, &actual);
+    if differences.is_empty() {
+        println!("No differences");
+        return 0;
+    }
+    print!("{}", format_differences(&differences, code_length));
+    1
 }
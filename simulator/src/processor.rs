@@ -1,14 +1,29 @@
 use crate::alu;
-use crate::instructions::parse_instruction;
+use crate::assembler::assemble;
+use crate::bus::{Bus, Device};
+use crate::console::Console;
+use crate::coredump::CoreDump;
+use crate::disassembler::disassemble;
+use crate::error::SimError;
+use crate::frame::{numbered_path, Frame};
+use crate::history::{History, UndoEntry};
+use crate::snapshot::Snapshot;
 use crate::statemachine::StateMachine;
+use crate::stats::Stats;
+use crate::trace::{Trace, TraceRecord};
 use crate::types::{
-    AddressSource, AluOperation, AluOutput, AluSource, ControlSignals, InstructionToken,
-    InstructionType, Opcode, PipelineRegisters, RegisterWriteSource, RegisterWriteTarget, RunState,
+    AddressSource, AluOperation, AluOutput, AluSource, Breakpoint, ControlSignals, ExecutionMode,
+    InstructionToken, InstructionType, Opcode, PipelineRegisters, RegisterWriteSource,
+    RegisterWriteTarget, RunState, RunSummary, SpecialOperation, State, StopCause, StopReason,
+    Watchpoint, WatchpointHit,
 };
-use log::{debug, error, info, trace};
+use crate::vcd::VcdWriter;
+use log::{debug, error, info, trace, warn};
 use std::fs::File;
 use std::io::Write;
-use time::OffsetDateTime;
+
+/// Address of the word holding the address of the interrupt handler
+pub const INTERRUPT_VECTOR: u16 = 0xFFFF;
 
 /// An AYU processor simulation
 pub struct Processor {
@@ -17,40 +32,67 @@ pub struct Processor {
     registers: [u16; 16],
     memory: [u16; 65536],
     instruction_register: u16,
+    instruction_address: u16,
     instruction_token: InstructionToken,
     control_signals: ControlSignals,
     state_machine: StateMachine,
     pipeline_registers: PipelineRegisters,
     breakpoint: u64,
+    breakpoints: Vec<Breakpoint>,
+    hit_breakpoint: Option<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    watchpoint_hit: Option<WatchpointHit>,
+    history: History,
+    recording: Option<UndoEntry>,
     dump_to_file: bool,
+    compact_dump: bool,
+    execution_mode: ExecutionMode,
+    trace: Option<Trace>,
+    /// The instruction being traced, written to the trace once it retires
+    traced: Option<TraceRecord>,
+    vcd: Option<VcdWriter>,
+    stats: Stats,
+    /// Clock cycle the instruction in progress was fetched on, None between instructions
+    fetch_cycle: Option<u64>,
+    /// Clock cycle after the furthest one run. Cycles before it that run again after stepping
+    /// back have already been counted and traced
+    counted_until: u64,
+    console: Console,
+    bus: Bus,
+    /// Clock cycles the devices on the bus have been ticked for
+    device_cycle: u64,
+    /// The PC an interrupt was taken at, returned to by rfi
+    epc: u16,
+    interrupts_enabled: bool,
+    /// Where the display's image is saved at halt, frames saved by the frame instruction
+    /// are numbered
+    frame_path: Option<String>,
+    frames_saved: u32,
 }
 
 impl Processor {
-    /// Create a processor by initialising memory to the contents of a .ayu file,
+    /// Create a processor by initialising memory to the assembled contents of a .ayu file,
     /// all registers are 0 so starts executing from first instruction
-    pub fn new_from_file(path_to_file: String, breakpoint: u64, dump_to_file: bool) -> Processor {
-        let instruction_string: String =
-            std::fs::read_to_string(path_to_file).expect("File not found");
-        let instruction_array: Vec<u16> = instruction_string
-            .split('\n')
-            .filter(|x| !x.starts_with('#') && !x.is_empty())
-            .map(|x| parse_instruction(x).expect("Invalid instruction"))
-            .collect();
-        let mut memory_array: [u16; 65536] = [0; 65536];
-        for (i, instruction) in instruction_array.iter().enumerate() {
-            memory_array[i] = *instruction;
-        }
+    pub fn new_from_file(
+        path_to_file: String,
+        breakpoint: u64,
+        dump_to_file: bool,
+    ) -> Result<Processor, SimError> {
+        let instruction_string: String = std::fs::read_to_string(path_to_file)?;
+        let instruction_array: Vec<u16> = assemble(&instruction_string)?;
+        let memory_array: [u16; 65536] = memory_image(&instruction_array)?;
         debug!("Memory contents:");
         memory_array.iter().enumerate().for_each(|(i, x)| match x {
             0 => (),
             _ => debug!("M{:#06X}: {:#06X}", i, x),
         });
-        Processor {
+        Ok(Processor {
             alu: alu::Alu::new(),
             clock_cycle: 0,
             registers: [0; 16],
             memory: memory_array,
             instruction_register: 0,
+            instruction_address: 0,
             instruction_token: InstructionToken {
                 instruction_type: InstructionType::Invalid,
                 opcode: Opcode::Invalid,
@@ -75,6 +117,7 @@ impl Processor {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             state_machine: StateMachine::new(),
             pipeline_registers: PipelineRegisters {
@@ -86,8 +129,29 @@ impl Processor {
                 alu_zero: false,
             },
             breakpoint,
+            breakpoints: Vec::new(),
+            hit_breakpoint: None,
+            watchpoints: Vec::new(),
+            watchpoint_hit: None,
+            history: History::new(0),
+            recording: None,
             dump_to_file,
-        }
+            compact_dump: false,
+            execution_mode: ExecutionMode::Cycle,
+            trace: None,
+            traced: None,
+            vcd: None,
+            stats: Stats::default(),
+            fetch_cycle: None,
+            counted_until: 0,
+            console: Console::stdio(),
+            bus: Bus::new(),
+            device_cycle: 0,
+            epc: 0,
+            interrupts_enabled: false,
+            frame_path: None,
+            frames_saved: 0,
+        })
     }
 
     /// Create a processor by initialising registers and memory to provided arrays,
@@ -114,6 +178,7 @@ impl Processor {
             registers: register_array,
             memory: memory_array,
             instruction_register: 0,
+            instruction_address: 0,
             instruction_token: InstructionToken {
                 instruction_type: InstructionType::Invalid,
                 opcode: Opcode::Invalid,
@@ -138,6 +203,7 @@ impl Processor {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             state_machine: StateMachine::new(),
             pipeline_registers: PipelineRegisters {
@@ -149,18 +215,144 @@ impl Processor {
                 alu_zero: false,
             },
             breakpoint: u64::MAX,
+            breakpoints: Vec::new(),
+            hit_breakpoint: None,
+            watchpoints: Vec::new(),
+            watchpoint_hit: None,
+            history: History::new(0),
+            recording: None,
             dump_to_file,
+            compact_dump: false,
+            execution_mode: ExecutionMode::Cycle,
+            trace: None,
+            traced: None,
+            vcd: None,
+            stats: Stats::default(),
+            fetch_cycle: None,
+            counted_until: 0,
+            console: Console::stdio(),
+            bus: Bus::new(),
+            device_cycle: 0,
+            epc: 0,
+            interrupts_enabled: false,
+            frame_path: None,
+            frames_saved: 0,
         }
     }
 
-    /// Runs 1 clock cycle, returns whether the processor should continue running for another cycle
-    pub fn run(&mut self) -> RunState {
+    /// Create a processor with the registers and memory held in a core dump
+    pub fn new_from_coredump(dump: &CoreDump, dump_to_file: bool) -> Result<Processor, SimError> {
+        let memory_array: [u16; 65536] = memory_image(&dump.memory)?;
+        Ok(Processor::new_from_array(
+            dump.registers,
+            memory_array,
+            dump_to_file,
+        ))
+    }
+
+    /// Create a processor that resumes from the state captured in a snapshot
+    pub fn new_from_snapshot(
+        snapshot: &Snapshot,
+        dump_to_file: bool,
+    ) -> Result<Processor, SimError> {
+        let mut processor = Processor::new_from_array([0; 16], [0; 65536], dump_to_file);
+        processor.restore(snapshot)?;
+        Ok(processor)
+    }
+
+    /// Execute instructions in an execution mode, cycle by cycle unless set
+    pub fn with_execution_mode(mut self, execution_mode: ExecutionMode) -> Processor {
+        self.execution_mode = execution_mode;
+        self
+    }
+
+    /// Map a device onto the bus, reads and writes to it's addresses go to the device instead
+    /// of RAM
+    pub fn with_device(
+        mut self,
+        start: u16,
+        device: Box<dyn Device>,
+    ) -> Result<Processor, SimError> {
+        self.bus.map(start, device)?;
+        Ok(self)
+    }
+
+    /// How instructions are being executed
+    pub fn execution_mode(&self) -> ExecutionMode {
+        self.execution_mode
+    }
+
+    /// Runs 1 clock cycle, or 1 instruction in functional mode, returns whether the processor
+    /// should continue running
+    pub fn run(&mut self) -> Result<RunState, SimError> {
+        // undoing the processor without the devices would put the two out of step
+        if self.history.limit() == 0 || self.bus.is_stateful() {
+            return self.step();
+        }
+        self.recording = Some(UndoEntry {
+            clock_cycle: self.clock_cycle,
+            registers: self.registers,
+            memory_writes: Vec::new(),
+            instruction_register: self.instruction_register,
+            instruction_address: self.instruction_address,
+            instruction_token: self.instruction_token.clone(),
+            control_signals: self.control_signals.clone(),
+            state_machine: self.state_machine.clone(),
+            pipeline_registers: self.pipeline_registers.clone(),
+            epc: self.epc,
+            interrupts_enabled: self.interrupts_enabled,
+            fetch_cycle: self.fetch_cycle,
+            traced: self.traced.clone(),
+        });
+        let running = self.step();
+        if let Some(entry) = self.recording.take() {
+            self.history.push(entry);
+        }
+        running
+    }
+
+    /// Run the next step of the current execution mode
+    fn step(&mut self) -> Result<RunState, SimError> {
+        let clock_cycle: u64 = self.clock_cycle;
+        // cycles run again after stepping back were counted the first time they ran
+        let counted: Option<Stats> = (clock_cycle < self.counted_until).then(|| self.stats.clone());
+        let cycles: u64 = self.stats.cycles;
+        let running = match self.execution_mode {
+            ExecutionMode::Cycle => self.cycle(),
+            ExecutionMode::Functional => self.execute_instruction(),
+        };
+        self.counted_until = self
+            .counted_until
+            .max(clock_cycle + self.stats.cycles - cycles);
+        if let Some(stats) = counted {
+            self.stats = stats;
+        }
+        if let Some(mut vcd) = self.vcd.take() {
+            let sampled = vcd.sample(clock_cycle, self);
+            self.vcd = Some(vcd);
+            sampled?;
+        }
+        running
+    }
+
+    /// Carry out the work of a single clock cycle
+    fn cycle(&mut self) -> Result<RunState, SimError> {
+        self.hit_breakpoint = None;
+        self.watchpoint_hit = None;
+        self.tick_devices(self.clock_cycle);
         // state machine shouldn't advance on first cycle
         if self.clock_cycle != 0 {
-            self.state_machine
-                .next_state(&self.instruction_token, self.pipeline_registers.alu_zero);
+            // interrupts are only taken between instructions
+            let interrupt: bool =
+                *self.state_machine.state() == State::PcRead && self.interrupt_requested();
+            self.state_machine.next_state(
+                &self.instruction_token,
+                self.pipeline_registers.alu_zero,
+                interrupt,
+            );
         }
         self.control_signals = self.state_machine.get_control_signals();
+        self.stats.record_cycle(self.state_machine.state());
         // Do ALU op if active
         if self.control_signals.alu_operation != AluOperation::Inactive {
             let source_a: u16 = match self.control_signals.alu_source {
@@ -185,22 +377,43 @@ impl Processor {
         }
         if self.control_signals.terminate {
             info!("Terminating processor, dumping core");
-            self.coredump(self.dump_to_file);
-            return RunState::Stop;
+            self.coredump(self.dump_to_file)?;
+            return Ok(self.stop(StopCause::Terminated));
         }
         if self.control_signals.decode {
-            debug!("Decoding instruction {:#06X}", self.instruction_register);
+            debug!(
+                "Decoding instruction {:#06X}: {}",
+                self.instruction_register,
+                disassemble(self.instruction_register)
+            );
             self.instruction_token = Processor::decode_instruction(self.instruction_register);
+            if self.instruction_token.opcode == Opcode::Invalid {
+                error!(
+                    "Invalid opcode in {:#06X} at {:#06X}",
+                    self.instruction_register, self.instruction_address
+                );
+                self.coredump(self.dump_to_file)?;
+                return Err(SimError::InvalidOpcode {
+                    pc: self.instruction_address,
+                    instruction: self.instruction_register,
+                });
+            }
         }
         if self.control_signals.memory_read {
             let address: u16 = match self.control_signals.address_source {
                 AddressSource::ProgramCounter => self.pipeline_registers.register_read_b,
                 AddressSource::Alu => self.pipeline_registers.alu_output,
+                AddressSource::InterruptVector => INTERRUPT_VECTOR,
+            };
+            let data: u16 = match self.control_signals.instruction_register_write {
+                true => self.load(address),
+                false => self.read_memory(address),
             };
-            let data = self.memory[address as usize];
             self.pipeline_registers.memory_data = data;
             if self.control_signals.instruction_register_write {
                 self.instruction_register = data;
+                self.instruction_address = address;
+                self.begin_instruction();
             }
             trace!(
                 "Read M{:#06X} = {:#06X}",
@@ -208,14 +421,21 @@ impl Processor {
                 self.pipeline_registers.memory_data
             );
         }
+        if self.control_signals.enter_interrupt {
+            debug!(
+                "Taking interrupt at {:#06X}",
+                self.pipeline_registers.register_read_b
+            );
+            self.epc = self.pipeline_registers.register_read_b;
+            self.interrupts_enabled = false;
+        }
         if self.control_signals.memory_write {
             let address: u16 = match self.control_signals.address_source {
                 AddressSource::ProgramCounter => self.pipeline_registers.register_read_b,
                 AddressSource::Alu => self.pipeline_registers.alu_output,
+                AddressSource::InterruptVector => INTERRUPT_VECTOR,
             };
-            let data = self.pipeline_registers.register_read_a;
-            self.memory[address as usize] = data;
-            trace!("Wrote M{:#06X} = {:#06X}", address, data);
+            self.write_memory(address, self.pipeline_registers.register_read_a);
         }
         if self.control_signals.register_write || self.control_signals.write_pc {
             let value_to_write: u16 = match self.control_signals.register_write_source {
@@ -243,14 +463,15 @@ impl Processor {
                     value_to_write,
                     register_to_write
                 );
-                self.registers[register_to_write] |= value_to_write << 8;
+                let value: u16 = self.registers[register_to_write] | value_to_write << 8;
+                self.write_register(register_to_write, value);
             } else {
                 trace!(
                     "Writing {:06X} to register {:01X}",
                     value_to_write,
                     register_to_write
                 );
-                self.registers[register_to_write] = value_to_write;
+                self.write_register(register_to_write, value_to_write);
             }
         }
 
@@ -267,35 +488,829 @@ impl Processor {
             self.registers[self.instruction_token.nibble_3 as usize];
 
         if self.control_signals.process_special {
-            match self.instruction_token.nibble_2 {
-                1 => {
-                    info!("Reached end of program");
-                    self.coredump(self.dump_to_file);
-                    return RunState::Stop;
-                }
-                _ => {
-                    error!("Unimplemented special instruction");
-                    self.coredump(self.dump_to_file);
-                    return RunState::Stop;
-                }
+            let running: RunState = self.process_special()?;
+            if running != RunState::Continue {
+                return Ok(running);
             }
         }
+        if *self.state_machine.state() == State::PcRead {
+            self.retire(self.clock_cycle)?;
+        }
         if self.clock_cycle > self.breakpoint {
             info!("Reached breakpoint");
-            self.coredump(self.dump_to_file);
-            return RunState::Stop;
+            self.coredump(self.dump_to_file)?;
+            return Ok(self.stop(StopCause::ClockCycle));
         }
         self.clock_cycle += 1;
-        RunState::Continue
+        self.check_stops()
+    }
+
+    /// Execute a whole instruction from the PcRead state to the next PcRead state, updating
+    /// registers, memory and the pipeline registers as the state machine would
+    fn execute_instruction(&mut self) -> Result<RunState, SimError> {
+        // only start at an instruction boundary, finishing any partly run instruction by cycle
+        if *self.state_machine.state() != State::PcRead {
+            return self.cycle();
+        }
+        self.hit_breakpoint = None;
+        self.watchpoint_hit = None;
+        if self.clock_cycle == 0 {
+            // the first PcRead state has not been run yet
+            self.clock_cycle = 1;
+            self.stats.cycles += 1;
+        }
+        self.tick_devices(self.clock_cycle);
+        if self.interrupt_requested() {
+            return self.take_interrupt();
+        }
+        // instruction fetch
+        let pc: u16 = self.registers[1];
+        self.instruction_register = self.load(pc);
+        self.instruction_address = pc;
+        self.pipeline_registers.memory_data = self.instruction_register;
+        self.begin_instruction();
+        let next_pc: AluOutput = self.alu.execute_operation(1, pc, &AluOperation::Add);
+        self.set_alu_output(&next_pc);
+        self.clock_cycle += 1;
+        // decode
+        // no per instruction logging, it would cost more than executing the instruction
+        self.instruction_token = Processor::decode_instruction(self.instruction_register);
+        self.stats.cycles += 2;
+        let token: InstructionToken = self.instruction_token.clone();
+        if token.opcode == Opcode::Invalid {
+            self.state_machine = StateMachine::from_parts(State::Decode, Opcode::Invalid);
+            self.coredump(self.dump_to_file)?;
+            return Err(SimError::InvalidOpcode {
+                pc,
+                instruction: self.instruction_register,
+            });
+        }
+        self.write_register(1, next_pc.result);
+        self.clock_cycle += 1;
+        let (n2, n3, n4) = (
+            token.nibble_2 as usize,
+            token.nibble_3 as usize,
+            token.nibble_4 as usize,
+        );
+        let byte: u16 = (token.nibble_3 as u16) << 4 | token.nibble_4 as u16;
+        // execute, counting the states the state machine would pass through
+        let states: u64 = match (&token.instruction_type, &token.opcode) {
+            (_, Opcode::SetLower) => {
+                self.write_register(n2, byte);
+                1
+            }
+            (_, Opcode::SetUpper) => {
+                self.write_register(n2, self.registers[n2] | byte << 8);
+                1
+            }
+            (_, Opcode::SetIfLess) | (_, Opcode::SetIfEqual) => {
+                let output: AluOutput = self.execute_alu(n3, n4, &AluOperation::Subtract);
+                let value: bool = match token.opcode {
+                    Opcode::SetIfLess => output.negative,
+                    _ => output.zero,
+                };
+                self.write_register(n2, value as u16);
+                2
+            }
+            (_, Opcode::LoadWord) => {
+                let address: u16 = self.memory_address(&token);
+                let data: u16 = self.read_memory(address);
+                self.pipeline_registers.memory_data = data;
+                self.write_register(n3, data);
+                3
+            }
+            (_, Opcode::SaveWord) => {
+                let address: u16 = self.memory_address(&token);
+                self.write_memory(address, self.registers[n3]);
+                2
+            }
+            (_, Opcode::SetPcIf) => match self.execute_alu(n3, n4, &AluOperation::Subtract).zero {
+                true => {
+                    self.write_register(1, token.nibble_2 as u16);
+                    2
+                }
+                false => 1,
+            },
+            (_, Opcode::Special) => {
+                self.state_machine = StateMachine::from_parts(State::Special, Opcode::Special);
+                self.control_signals = self.state_machine.get_control_signals();
+                self.pipeline_registers.register_read_b = self.registers[n4];
+                self.pipeline_registers.register_read_a = self.registers[n3];
+                self.tick_devices(self.clock_cycle);
+                let running: RunState = self.process_special()?;
+                if running != RunState::Continue {
+                    // a halt finishes in the Special state, without a PcRead after it
+                    self.stats.cycles += 1;
+                    return Ok(running);
+                }
+                1
+            }
+            _ => {
+                let operation: AluOperation =
+                    AluOperation::from_opcode(&token.opcode).unwrap_or(AluOperation::Inactive);
+                let output: AluOutput = self.execute_alu(n3, n4, &operation);
+                self.write_register(n2, output.result);
+                2
+            }
+        };
+        // the PcRead state that starts the next instruction
+        self.state_machine = StateMachine::from_parts(State::PcRead, token.opcode);
+        self.control_signals = self.state_machine.get_control_signals();
+        self.pipeline_registers.register_read_b = self.registers[1];
+        self.pipeline_registers.register_read_a = self.registers[n3];
+        self.stats.cycles += states + 1;
+        self.tick_devices(self.clock_cycle + states);
+        self.retire(self.clock_cycle + states)?;
+        if self.clock_cycle + states > self.breakpoint {
+            info!("Reached breakpoint");
+            self.coredump(self.dump_to_file)?;
+            return Ok(self.stop(StopCause::ClockCycle));
+        }
+        self.clock_cycle += states + 1;
+        self.check_stops()
+    }
+
+    /// Save the PC and jump to the interrupt handler, passing through the InterruptSave and
+    /// InterruptVector states to the PcRead state before the handler's first instruction
+    fn take_interrupt(&mut self) -> Result<RunState, SimError> {
+        let pc: u16 = self.registers[1];
+        debug!("Taking interrupt at {:#06X}", pc);
+        self.epc = pc;
+        self.interrupts_enabled = false;
+        let handler: u16 = self.read_memory(INTERRUPT_VECTOR);
+        self.pipeline_registers.memory_data = handler;
+        self.write_register(1, handler);
+        self.state_machine =
+            StateMachine::from_parts(State::PcRead, self.state_machine.opcode().clone());
+        self.control_signals = self.state_machine.get_control_signals();
+        self.pipeline_registers.register_read_b = handler;
+        self.stats.cycles += 3;
+        if self.clock_cycle + 2 > self.breakpoint {
+            info!("Reached breakpoint");
+            self.coredump(self.dump_to_file)?;
+            return Ok(self.stop(StopCause::ClockCycle));
+        }
+        self.clock_cycle += 3;
+        self.check_stops()
+    }
+
+    /// Whether interrupts are enabled and a device is requesting one
+    fn interrupt_requested(&mut self) -> bool {
+        self.interrupts_enabled && self.bus.interrupt()
+    }
+
+    /// Run an ALU operation on two registers, as the ALU would after reading them
+    fn execute_alu(&mut self, a: usize, b: usize, operation: &AluOperation) -> AluOutput {
+        let output: AluOutput =
+            self.alu
+                .execute_operation(self.registers[a], self.registers[b], operation);
+        self.set_alu_output(&output);
+        output
+    }
+
+    /// The address a load or store accesses, the offset plus the base register. Devices are
+    /// ticked up to the following cycle, where the state machine would access memory
+    fn memory_address(&mut self, token: &InstructionToken) -> u16 {
+        self.tick_devices(self.clock_cycle + 1);
+        let output: AluOutput = self.alu.execute_operation(
+            token.nibble_2 as u16,
+            self.registers[token.nibble_4 as usize],
+            &AluOperation::Add,
+        );
+        self.set_alu_output(&output);
+        output.result
+    }
+
+    /// Tick the devices once for every clock cycle before the given one, so they see the same
+    /// time in both execution modes
+    fn tick_devices(&mut self, clock_cycle: u64) {
+        while self.device_cycle < clock_cycle {
+            self.bus.tick();
+            self.device_cycle += 1;
+        }
+    }
+
+    fn set_alu_output(&mut self, output: &AluOutput) {
+        self.pipeline_registers.alu_output = output.result;
+        self.pipeline_registers.alu_zero = output.zero;
+        self.pipeline_registers.alu_negative = output.negative;
+    }
+
+    fn write_register(&mut self, register: usize, value: u16) {
+        let old_value: u16 = self.registers[register];
+        self.registers[register] = value;
+        self.check_register_watchpoints(register, old_value);
+        if let Some(record) = &mut self.traced {
+            record.register_writes.push((register as u8, value));
+        }
+    }
+
+    /// Read a word from the device mapped at an address, or RAM if there isn't one
+    fn load(&mut self, address: u16) -> u16 {
+        match self.bus.read(address) {
+            Some(data) => data,
+            None => self.memory[address as usize],
+        }
+    }
+
+    /// The word at an address, without the side effects a device might have on a read
+    pub fn peek(&self, address: u16) -> u16 {
+        match self.bus.peek(address) {
+            Some(data) => data,
+            None => self.memory[address as usize],
+        }
+    }
+
+    /// Read a word of data from memory, instruction fetches do not go through here
+    fn read_memory(&mut self, address: u16) -> u16 {
+        let data: u16 = self.load(address);
+        self.check_memory_watchpoints(address, false, data, data);
+        self.stats.memory_reads += 1;
+        if let Some(record) = &mut self.traced {
+            record.memory_reads.push((address, data));
+        }
+        data
+    }
+
+    /// Write a word of memory, recording the old value so it can be undone. Writes to devices
+    /// are passed on to them and can't be undone
+    fn write_memory(&mut self, address: u16, data: u16) {
+        self.check_memory_watchpoints(address, true, self.peek(address), data);
+        if !self.bus.write(address, data) {
+            if let Some(entry) = &mut self.recording {
+                entry
+                    .memory_writes
+                    .push((address, self.memory[address as usize]));
+            }
+            self.memory[address as usize] = data;
+        }
+        self.stats.memory_writes += 1;
+        if let Some(record) = &mut self.traced {
+            record.memory_writes.push((address, data));
+        }
+        trace!("Wrote M{:#06X} = {:#06X}", address, data);
+    }
+
+    /// Start counting the instruction that has just been fetched, and recording it if tracing
+    fn begin_instruction(&mut self) {
+        self.fetch_cycle = Some(self.clock_cycle);
+        self.stats.instruction_fetches += 1;
+        if self.trace.is_some() {
+            self.traced = Some(TraceRecord::new(
+                self.clock_cycle,
+                self.instruction_address,
+                self.instruction_register,
+            ));
+        }
+    }
+
+    /// Count the instruction that has just finished on a clock cycle, and write it's record
+    /// to the trace
+    fn retire(&mut self, finished_on: u64) -> Result<(), SimError> {
+        let fetch_cycle: u64 = match self.fetch_cycle.take() {
+            Some(fetch_cycle) => fetch_cycle,
+            None => return Ok(()),
+        };
+        let opcode: &Opcode = &self.instruction_token.opcode;
+        if *opcode == Opcode::SetPcIf {
+            // the ALU is idle after the comparison so it's result is still held
+            match self.pipeline_registers.alu_zero {
+                true => self.stats.branches_taken += 1,
+                false => self.stats.branches_not_taken += 1,
+            }
+        }
+        self.stats.record_instruction(
+            opcode,
+            self.instruction_address,
+            finished_on - fetch_cycle + 1,
+        );
+        if let (Some(trace), Some(record)) = (&mut self.trace, self.traced.take()) {
+            // an instruction run again after stepping back has already been traced
+            if finished_on >= self.counted_until {
+                trace.record(&record)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Carry out the special instruction that has been decoded
+    fn process_special(&mut self) -> Result<RunState, SimError> {
+        // console instructions use the register in nibble 3, already read into register_read_a
+        let register: usize = self.instruction_token.nibble_3 as usize;
+        let value: u16 = self.pipeline_registers.register_read_a;
+        match SpecialOperation::from_u8(self.instruction_token.nibble_2) {
+            Some(SpecialOperation::Halt) => {
+                let exit_code: u8 =
+                    self.instruction_token.nibble_3 << 4 | self.instruction_token.nibble_4;
+                info!("Reached end of program, exit code {}", exit_code);
+                self.retire(self.clock_cycle)?;
+                if let Some(path) = &self.frame_path {
+                    self.save_frame(path)?;
+                }
+                self.coredump(self.dump_to_file)?;
+                Ok(self.stop(StopCause::Halt(exit_code)))
+            }
+            Some(SpecialOperation::PutChar) => {
+                self.console
+                    .write(&((value & 0xFF) as u8 as char).to_string())?;
+                Ok(RunState::Continue)
+            }
+            Some(SpecialOperation::PutDecimal) => {
+                self.console.write(&value.to_string())?;
+                Ok(RunState::Continue)
+            }
+            Some(SpecialOperation::PutHex) => {
+                self.console.write(&format!("{:04X}", value))?;
+                Ok(RunState::Continue)
+            }
+            Some(SpecialOperation::GetChar) => {
+                let input: u16 = self.console.read_char()?;
+                self.write_register(register, input);
+                Ok(RunState::Continue)
+            }
+            Some(SpecialOperation::GetDecimal) => {
+                let input: u16 = self.console.read_decimal()?;
+                self.write_register(register, input);
+                Ok(RunState::Continue)
+            }
+            Some(SpecialOperation::ReturnFromInterrupt) => {
+                self.write_register(1, self.epc);
+                self.interrupts_enabled = true;
+                Ok(RunState::Continue)
+            }
+            Some(SpecialOperation::EnableInterrupts) => {
+                self.interrupts_enabled = true;
+                Ok(RunState::Continue)
+            }
+            Some(SpecialOperation::DisableInterrupts) => {
+                self.interrupts_enabled = false;
+                Ok(RunState::Continue)
+            }
+            Some(SpecialOperation::SaveFrame) => {
+                if let Some(path) = &self.frame_path {
+                    self.frames_saved += 1;
+                    self.save_frame(&numbered_path(path, self.frames_saved))?;
+                }
+                Ok(RunState::Continue)
+            }
+            None => {
+                error!("Unimplemented special instruction");
+                self.coredump(self.dump_to_file)?;
+                Err(SimError::InvalidSpecial {
+                    pc: self.instruction_address,
+                    instruction: self.instruction_register,
+                })
+            }
+        }
+    }
+
+    /// Save the image shown by the display device to a file
+    fn save_frame(&self, path: &str) -> Result<(), SimError> {
+        match self.bus.frame() {
+            Some(frame) => {
+                info!("Saving frame to {}", path);
+                frame.save(path)
+            }
+            None => {
+                warn!("No display to save a frame from");
+                Ok(())
+            }
+        }
+    }
+
+    /// Stop if a watchpoint was hit, or if a breakpoint is met by the next instruction
+    fn check_stops(&mut self) -> Result<RunState, SimError> {
+        if let Some(hit) = &self.watchpoint_hit {
+            info!(
+                "Watchpoint {} hit by {} at {:#06X}: {:#06X} -> {:#06X}",
+                hit.watchpoint,
+                disassemble(hit.instruction),
+                hit.pc,
+                hit.old_value,
+                hit.new_value
+            );
+            self.coredump(self.dump_to_file)?;
+            return Ok(RunState::Stop(StopReason {
+                pc: hit.pc,
+                instruction: hit.instruction,
+                cause: StopCause::Watchpoint(hit.clone()),
+            }));
+        }
+        // the PC has just been read so the next instruction is about to be fetched
+        if *self.state_machine.state() == State::PcRead {
+            let instruction: u16 = self.peek(self.registers[1]);
+            if let Some(breakpoint) = self
+                .breakpoints
+                .iter()
+                .find(|x| x.is_met(&self.registers, instruction))
+            {
+                info!(
+                    "Reached breakpoint {} at PC {:#06X}",
+                    breakpoint, self.registers[1]
+                );
+                self.hit_breakpoint = Some(breakpoint.clone());
+                self.coredump(self.dump_to_file)?;
+                return Ok(RunState::Stop(StopReason {
+                    cause: StopCause::Breakpoint(breakpoint.clone()),
+                    pc: self.registers[1],
+                    instruction,
+                }));
+            }
+        }
+        Ok(RunState::Continue)
+    }
+
+    /// Stop at the instruction currently being executed
+    fn stop(&self, cause: StopCause) -> RunState {
+        RunState::Stop(StopReason {
+            cause,
+            pc: self.instruction_address,
+            instruction: self.instruction_register,
+        })
+    }
+
+    /// Stop before executing any instruction that meets the breakpoint
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Remove a breakpoint, returns whether it was set
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let count: usize = self.breakpoints.len();
+        self.breakpoints.retain(|x| x != breakpoint);
+        count != self.breakpoints.len()
+    }
+
+    /// Remove every breakpoint
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Stop after any access to a memory address or register that matches the watchpoint,
+    /// instruction fetches are not counted as memory reads
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+    }
+
+    /// Remove a watchpoint, returns whether it was set
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        let count: usize = self.watchpoints.len();
+        self.watchpoints.retain(|x| x != watchpoint);
+        count != self.watchpoints.len()
+    }
+
+    /// Remove every watchpoint
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    /// All watchpoints currently set
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// The access that triggered a watchpoint in the last clock cycle, if any
+    pub fn watchpoint_hit(&self) -> Option<&WatchpointHit> {
+        self.watchpoint_hit.as_ref()
+    }
+
+    /// Record a hit if a memory access matches any watchpoint
+    fn check_memory_watchpoints(
+        &mut self,
+        address: u16,
+        write: bool,
+        old_value: u16,
+        new_value: u16,
+    ) {
+        if self.watchpoint_hit.is_some() {
+            return;
+        }
+        if let Some(watchpoint) = self
+            .watchpoints
+            .iter()
+            .find(|x| x.watches_memory(address, write))
+        {
+            self.watchpoint_hit = Some(WatchpointHit {
+                watchpoint: watchpoint.clone(),
+                pc: self.instruction_address,
+                instruction: self.instruction_register,
+                old_value,
+                new_value,
+            });
+        }
+    }
+
+    /// Record a hit if a register written to is watched
+    fn check_register_watchpoints(&mut self, register: usize, old_value: u16) {
+        let watchpoint = Watchpoint::Register(register as u8);
+        if self.watchpoint_hit.is_none() && self.watchpoints.contains(&watchpoint) {
+            self.watchpoint_hit = Some(WatchpointHit {
+                watchpoint,
+                pc: self.instruction_address,
+                instruction: self.instruction_register,
+                old_value,
+                new_value: self.registers[register],
+            });
+        }
+    }
+
+    /// All breakpoints currently set
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// The breakpoint that stopped the last clock cycle, if any
+    pub fn hit_breakpoint(&self) -> Option<&Breakpoint> {
+        self.hit_breakpoint.as_ref()
+    }
+
+    /// Runs clock cycles until the current instruction has finished and the state machine
+    /// has returned to PcRead for the next one
+    pub fn step_instruction(&mut self) -> Result<RunState, SimError> {
+        let mut left_pc_read: bool = self.execution_mode == ExecutionMode::Functional;
+        loop {
+            let running: RunState = self.run()?;
+            let at_pc_read: bool = *self.state_machine.state() == State::PcRead;
+            if running != RunState::Continue || (left_pc_read && at_pc_read) {
+                return Ok(running);
+            }
+            left_pc_read |= !at_pc_read;
+        }
+    }
+
+    /// Run until the processor stops, returning a Watchdog error if it is still running
+    /// after max_cycles clock cycles
+    pub fn run_until_halt(&mut self, max_cycles: u64) -> Result<RunSummary, SimError> {
+        self.run_until(max_cycles, |_| false)
+    }
+
+    /// Run a number of clock cycles, stopping early if the processor stops
+    pub fn run_cycles(&mut self, cycles: u64) -> Result<RunSummary, SimError> {
+        self.run_while(cycles, |_| true)
+    }
+
+    /// Run a number of instructions, stopping early if the processor stops
+    pub fn run_instructions(&mut self, instructions: u64) -> Result<RunSummary, SimError> {
+        let mut summary = RunSummary {
+            state: RunState::Continue,
+            cycles: 0,
+            instructions: 0,
+        };
+        while summary.instructions < instructions && summary.state == RunState::Continue {
+            let start: u64 = self.clock_cycle;
+            summary.state = self.step_instruction()?;
+            summary.cycles += self.clock_cycle - start;
+            if *self.state_machine.state() == State::PcRead {
+                summary.instructions += 1;
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Run until the predicate holds or the processor stops, returning a Watchdog error
+    /// if neither happens within max_cycles clock cycles
+    pub fn run_until(
+        &mut self,
+        max_cycles: u64,
+        mut predicate: impl FnMut(&Processor) -> bool,
+    ) -> Result<RunSummary, SimError> {
+        let summary = self.run_while(max_cycles, |processor| !predicate(processor))?;
+        if summary.state == RunState::Continue && !predicate(self) {
+            return Err(SimError::Watchdog { cycles: max_cycles });
+        }
+        Ok(summary)
+    }
+
+    /// Run clock cycles while the condition holds, up to max_cycles, stopping early
+    /// if the processor stops. In functional mode the last instruction may take the run
+    /// a few cycles past max_cycles
+    fn run_while(
+        &mut self,
+        max_cycles: u64,
+        mut condition: impl FnMut(&Processor) -> bool,
+    ) -> Result<RunSummary, SimError> {
+        let mut summary = RunSummary {
+            state: RunState::Continue,
+            cycles: 0,
+            instructions: 0,
+        };
+        let start: u64 = self.clock_cycle;
+        while self.clock_cycle - start < max_cycles && condition(self) {
+            let previous: State = self.state_machine.state().clone();
+            summary.state = self.run()?;
+            summary.cycles = self.clock_cycle - start;
+            let functional: bool = self.execution_mode == ExecutionMode::Functional;
+            if (previous != State::PcRead || functional)
+                && *self.state_machine.state() == State::PcRead
+            {
+                summary.instructions += 1;
+            }
+            if summary.state != RunState::Continue {
+                break;
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Capture the full state of the processor so it can be restored later
+    pub fn snapshot(&self) -> Snapshot {
+        let token: &InstructionToken = &self.instruction_token;
+        Snapshot {
+            clock_cycle: self.clock_cycle,
+            breakpoint: self.breakpoint,
+            registers: self.registers,
+            memory: self.memory.to_vec(),
+            instruction_register: self.instruction_register,
+            instruction_address: self.instruction_address,
+            decoded_instruction: (token.opcode.to_u8() as u16) << 12
+                | (token.nibble_2 as u16) << 8
+                | (token.nibble_3 as u16) << 4
+                | token.nibble_4 as u16,
+            state: self.state_machine.state().clone(),
+            opcode: self.state_machine.opcode().clone(),
+            pipeline_registers: self.pipeline_registers.clone(),
+            epc: self.epc,
+            interrupts_enabled: self.interrupts_enabled,
+            fetch_cycle: self.fetch_cycle,
+        }
+    }
+
+    /// Return the processor to the state captured in a snapshot, recorded history is discarded
+    /// as it no longer leads to the restored state
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), SimError> {
+        self.memory = memory_image(&snapshot.memory)?;
+        self.clock_cycle = snapshot.clock_cycle;
+        // devices aren't part of a snapshot, they carry on from the restored clock cycle
+        self.device_cycle = snapshot.clock_cycle;
+        self.breakpoint = snapshot.breakpoint;
+        self.registers = snapshot.registers;
+        self.instruction_register = snapshot.instruction_register;
+        self.instruction_address = snapshot.instruction_address;
+        self.instruction_token = Processor::decode_instruction(snapshot.decoded_instruction);
+        self.state_machine =
+            StateMachine::from_parts(snapshot.state.clone(), snapshot.opcode.clone());
+        self.control_signals = self.state_machine.get_control_signals();
+        self.pipeline_registers = snapshot.pipeline_registers.clone();
+        self.epc = snapshot.epc;
+        self.interrupts_enabled = snapshot.interrupts_enabled;
+        self.fetch_cycle = snapshot.fetch_cycle;
+        self.hit_breakpoint = None;
+        self.watchpoint_hit = None;
+        self.history.clear();
+        debug!("Restored snapshot at clock cycle {}", self.clock_cycle);
+        Ok(())
+    }
+
+    /// Keep enough history to step back up to a number of clock cycles, 0 stops recording
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history.set_limit(limit);
+    }
+
+    /// Maximum number of clock cycles that can be stepped back
+    pub fn history_limit(&self) -> usize {
+        self.history.limit()
+    }
+
+    /// Number of clock cycles that can currently be stepped back
+    pub fn history_length(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Whether a device that can change state is mapped, no history is recorded while one is
+    pub fn has_stateful_devices(&self) -> bool {
+        self.bus.is_stateful()
+    }
+
+    /// Undo the last clock cycle, returns false if there is no history to undo
+    pub fn step_back(&mut self) -> bool {
+        let entry: UndoEntry = match self.history.pop() {
+            Some(entry) => entry,
+            None => return false,
+        };
+        for (address, value) in entry.memory_writes.into_iter().rev() {
+            self.memory[address as usize] = value;
+        }
+        self.clock_cycle = entry.clock_cycle;
+        self.device_cycle = entry.clock_cycle;
+        self.registers = entry.registers;
+        self.instruction_register = entry.instruction_register;
+        self.instruction_address = entry.instruction_address;
+        self.instruction_token = entry.instruction_token;
+        self.control_signals = entry.control_signals;
+        self.state_machine = entry.state_machine;
+        self.pipeline_registers = entry.pipeline_registers;
+        self.epc = entry.epc;
+        self.interrupts_enabled = entry.interrupts_enabled;
+        self.fetch_cycle = entry.fetch_cycle;
+        self.traced = entry.traced;
+        self.hit_breakpoint = None;
+        self.watchpoint_hit = None;
+        trace!("Stepped back to clock cycle {}", self.clock_cycle);
+        true
+    }
+
+    /// Undo clock cycles until the state machine is back in PcRead for the previous instruction,
+    /// the reverse of step_instruction. Returns false if the history ran out first
+    pub fn step_back_instruction(&mut self) -> bool {
+        loop {
+            if !self.step_back() {
+                return false;
+            }
+            if *self.state_machine.state() == State::PcRead {
+                return true;
+            }
+        }
+    }
+
+    /// Undo clock cycles until the clock cycle count is back to a value,
+    /// returns false if the history ran out first
+    pub fn rewind_to(&mut self, clock_cycle: u64) -> bool {
+        while self.clock_cycle > clock_cycle {
+            if !self.step_back() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Number of clock cycles that have been run
+    pub fn clock_cycle(&self) -> u64 {
+        self.clock_cycle
+    }
+
+    /// Current value of every register
+    pub fn registers(&self) -> &[u16; 16] {
+        &self.registers
+    }
+
+    /// Overwrite the value of a register
+    pub fn set_register(&mut self, register: usize, value: u16) {
+        self.registers[register] = value;
+    }
+
+    /// The PC the last interrupt was taken at
+    pub fn epc(&self) -> u16 {
+        self.epc
+    }
+
+    /// Whether a device requesting an interrupt will be handled
+    pub fn interrupts_enabled(&self) -> bool {
+        self.interrupts_enabled
+    }
+
+    /// Save the display's image to a file at halt, and to numbered files alongside it on the
+    /// frame instruction. None stops saving frames
+    pub fn set_frame_path(&mut self, path: Option<String>) {
+        self.frame_path = path;
+    }
+
+    /// The image shown by the display device, None if there isn't one
+    pub fn frame(&self) -> Option<Frame> {
+        self.bus.frame()
+    }
+
+    /// Current contents of memory
+    pub fn memory(&self) -> &[u16; 65536] {
+        &self.memory
+    }
+
+    /// Overwrite the value of a word of memory, or write to the device mapped at the address
+    pub fn set_memory(&mut self, address: u16, value: u16) {
+        if !self.bus.write(address, value) {
+            self.memory[address as usize] = value;
+        }
+    }
+
+    /// The instruction most recently fetched from memory
+    pub fn instruction_register(&self) -> u16 {
+        self.instruction_register
+    }
+
+    /// The state of the FSM for the last clock cycle run
+    pub fn state(&self) -> &State {
+        self.state_machine.state()
+    }
+
+    /// The control signals used for the last clock cycle run
+    pub fn control_signals(&self) -> &ControlSignals {
+        &self.control_signals
+    }
+
+    /// Current values of the intermediate pipeline registers
+    pub fn pipeline_registers(&self) -> &PipelineRegisters {
+        &self.pipeline_registers
     }
 
     /// Convert an instruction to an InstructionToken
-    fn decode_instruction(instruction: u16) -> InstructionToken {
-        let opcode: Opcode =
-            Opcode::from_u8(u8::try_from((instruction & 0xF000) >> 12).expect("Invalid byte 1"));
-        let nibble_2: u8 = u8::try_from((instruction & 0x0F00) >> 8).expect("Invalid byte 2");
-        let nibble_3: u8 = u8::try_from((instruction & 0x00F0) >> 4).expect("Invalid byte 3");
-        let nibble_4: u8 = u8::try_from(instruction & 0x000F).expect("Invalid byte 4");
+    pub fn decode_instruction(instruction: u16) -> InstructionToken {
+        let opcode: Opcode = Opcode::from_u8(((instruction & 0xF000) >> 12) as u8);
+        let nibble_2: u8 = ((instruction & 0x0F00) >> 8) as u8;
+        let nibble_3: u8 = ((instruction & 0x00F0) >> 4) as u8;
+        let nibble_4: u8 = (instruction & 0x000F) as u8;
         let instruction_type: InstructionType = InstructionType::from_opcode(&opcode);
         InstructionToken {
             opcode,
@@ -309,26 +1324,73 @@ impl Processor {
     /// Dump the current state of a processor to a file
     ///
     /// Returns current register and memory state as a pair of arrays
-    pub fn coredump(&self, write_to_file: bool) -> (Vec<u16>, Vec<u16>) {
-        let mut dump = format!("Core dump at time: {:#?}\n", OffsetDateTime::now_utc());
-        dump.push_str(format!("Clock cycle: {:#?}\n", self.clock_cycle).as_str());
-        dump.push_str("\nRegisters:\n");
-        let mut dump_registers = Vec::new();
-        for (i, register) in self.registers.iter().enumerate() {
-            dump.push_str(format!("R{:#02X}: {:#06X}\n", i, register).as_str());
-            dump_registers.push(*register);
-        }
-        dump.push_str("\nMemory:\n");
-        let mut dump_memory = Vec::new();
-        for (i, memory) in self.memory.iter().enumerate() {
-            dump.push_str(format!("M{:#06X}: {:#06X}\n", i, memory).as_str());
-            dump_memory.push(*memory);
-        }
+    pub fn coredump(&self, write_to_file: bool) -> Result<(Vec<u16>, Vec<u16>), SimError> {
+        let dump: CoreDump = self.to_core_dump();
         if write_to_file {
-            let mut file = File::create("core.dump").expect("Could not create coredump file");
-            file.write_all(dump.as_bytes())
-                .expect("Could not write to coredump file");
+            let text: String = match self.compact_dump {
+                true => dump.to_compact_text(),
+                false => dump.to_text(),
+            };
+            let mut file = File::create("core.dump")?;
+            file.write_all(text.as_bytes())?;
         }
-        (dump_registers, dump_memory)
+        Ok((dump.registers.to_vec(), dump.memory))
+    }
+
+    /// The current registers and memory, as they would be written to a core dump
+    pub fn to_core_dump(&self) -> CoreDump {
+        CoreDump {
+            clock_cycle: self.clock_cycle,
+            registers: self.registers,
+            memory: self.memory.to_vec(),
+        }
+    }
+
+    /// Write a record of every instruction that retires to a trace, None stops tracing
+    pub fn set_trace(&mut self, trace: Option<Trace>) {
+        self.trace = trace;
+        self.traced = None;
+    }
+
+    /// Stop tracing, returning the trace so it can be flushed
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.traced = None;
+        self.trace.take()
+    }
+
+    /// Read and write console instructions' input and output through different streams
+    pub fn set_console(&mut self, console: Console) {
+        self.console = console;
+    }
+
+    /// Performance counters for everything run so far
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Dump the signals of every clock cycle run to a VCD file, None stops dumping. In
+    /// functional mode only the state at the end of each instruction is dumped
+    pub fn set_vcd(&mut self, vcd: Option<VcdWriter>) {
+        self.vcd = vcd;
+    }
+
+    /// Stop dumping signals, returning the writer so it can be flushed
+    pub fn take_vcd(&mut self) -> Option<VcdWriter> {
+        self.vcd.take()
+    }
+
+    /// Write core dumps with runs of zero words collapsed onto one line
+    pub fn set_compact_dump(&mut self, compact_dump: bool) {
+        self.compact_dump = compact_dump;
+    }
+}
+
+/// Copy a memory image into a full sized memory array
+fn memory_image(words: &[u16]) -> Result<[u16; 65536], SimError> {
+    let mut memory: [u16; 65536] = [0; 65536];
+    if words.len() > memory.len() {
+        return Err(SimError::ProgramTooLarge { words: words.len() });
     }
+    memory[..words.len()].copy_from_slice(words);
+    Ok(memory)
 }
@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::disassembler::disassemble;
+use crate::stats::Stats;
+
+/// Name given to the instructions before the first label
+const START_REGION: &str = "(start)";
+
+/// Cycles attributed to an address, or to the region between two labels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hotspot {
+    /// The address, or the label that starts the region
+    pub name: String,
+    pub start: u16,
+    /// Last address included
+    pub end: u16,
+    pub instructions: u64,
+    pub cycles: u64,
+}
+
+/// Attributes the clock cycles recorded in a processor's stats to the instructions and labelled
+/// regions of the program that ran
+pub struct Profile<'a> {
+    stats: &'a Stats,
+    memory: &'a [u16],
+    /// Every label, grouped by the address it names
+    labels: BTreeMap<u16, Vec<String>>,
+}
+
+impl<'a> Profile<'a> {
+    pub fn new(stats: &'a Stats, memory: &'a [u16], labels: &HashMap<String, u16>) -> Profile<'a> {
+        let mut grouped: BTreeMap<u16, Vec<String>> = BTreeMap::new();
+        for (label, address) in labels {
+            grouped.entry(*address).or_default().push(label.clone());
+        }
+        grouped.values_mut().for_each(|x| x.sort());
+        Profile {
+            stats,
+            memory,
+            labels: grouped,
+        }
+    }
+
+    /// Cycles taken by every instruction, the instructions taking most first
+    pub fn hotspots(&self) -> Vec<Hotspot> {
+        let mut hotspots: Vec<Hotspot> = self
+            .stats
+            .pc_cycles
+            .iter()
+            .map(|(address, cycles)| Hotspot {
+                name: format!("{:#06X}", address),
+                start: *address,
+                end: *address,
+                instructions: self.stats.pc_histogram.get(address).copied().unwrap_or(0),
+                cycles: *cycles,
+            })
+            .collect();
+        hotspots.sort_by(|a, b| b.cycles.cmp(&a.cycles).then(a.start.cmp(&b.start)));
+        hotspots
+    }
+
+    /// Cycles taken in each region that starts at a label and runs up to the next, the regions
+    /// taking most first. Regions where nothing ran are left out
+    pub fn regions(&self) -> Vec<Hotspot> {
+        let mut starts: Vec<(u16, String)> = self
+            .labels
+            .iter()
+            .map(|(address, labels)| (*address, labels.join(", ")))
+            .collect();
+        if starts.first().is_none_or(|(address, _)| *address != 0) {
+            starts.insert(0, (0, String::from(START_REGION)));
+        }
+        let mut regions: Vec<Hotspot> = Vec::new();
+        for (i, (start, name)) in starts.iter().enumerate() {
+            let end: u16 = starts.get(i + 1).map_or(u16::MAX, |(next, _)| next - 1);
+            let mut region = Hotspot {
+                name: name.clone(),
+                start: *start,
+                end,
+                instructions: 0,
+                cycles: 0,
+            };
+            for (address, cycles) in self.stats.pc_cycles.range(start..=&end) {
+                region.cycles += cycles;
+                region.instructions += self.stats.pc_histogram.get(address).copied().unwrap_or(0);
+            }
+            if region.instructions != 0 {
+                regions.push(region);
+            }
+        }
+        regions.sort_by(|a, b| b.cycles.cmp(&a.cycles).then(a.start.cmp(&b.start)));
+        regions
+    }
+
+    /// Cycles attributed to instructions, the first PcRead state of a run is not part of any
+    fn total_cycles(&self) -> u64 {
+        self.stats.pc_cycles.values().sum()
+    }
+
+    fn percentage(&self, cycles: u64) -> f64 {
+        match self.total_cycles() {
+            0 => 0.0,
+            total => cycles as f64 * 100.0 / total as f64,
+        }
+    }
+
+    /// Table of the instructions taking the most cycles, up to a limit
+    pub fn format_hotspots(&self, limit: usize) -> String {
+        let mut text = format!(
+            "{:<10}{:>10}{:>8}{:>10}  Instruction\n",
+            "Address", "Cycles", "%", "Count"
+        );
+        for hotspot in self.hotspots().iter().take(limit) {
+            writeln!(
+                text,
+                "{:<10}{:>10}{:>7.1}%{:>10}  {}",
+                hotspot.name,
+                hotspot.cycles,
+                self.percentage(hotspot.cycles),
+                hotspot.instructions,
+                disassemble(self.memory[hotspot.start as usize])
+            )
+            .unwrap();
+        }
+        text
+    }
+
+    /// Table of the cycles taken in each labelled region
+    pub fn format_regions(&self) -> String {
+        let mut text = format!(
+            "{:<20}{:>8}{:>8}{:>10}{:>8}{:>10}\n",
+            "Region", "Start", "End", "Cycles", "%", "Count"
+        );
+        for region in self.regions() {
+            writeln!(
+                text,
+                "{:<20}{:>8}{:>8}{:>10}{:>7.1}%{:>10}",
+                region.name,
+                format!("{:#06X}", region.start),
+                format!("{:#06X}", region.end),
+                region.cycles,
+                self.percentage(region.cycles),
+                region.instructions
+            )
+            .unwrap();
+        }
+        text
+    }
+
+    /// Disassembly of the program with the cycles and count of every instruction beside it and
+    /// labels on their own lines. Runs of 4 or more zero words that never ran are collapsed
+    pub fn annotate(&self) -> String {
+        let program_length: usize = self
+            .memory
+            .iter()
+            .rposition(|x| *x != 0)
+            .map_or(0, |x| x + 1);
+        let executed_length: usize = self
+            .stats
+            .pc_cycles
+            .keys()
+            .next_back()
+            .map_or(0, |x| *x as usize + 1);
+        let length: usize = program_length.max(executed_length);
+        let mut text = String::new();
+        let mut address: usize = 0;
+        while address < length {
+            let idle_zeros: usize = (address..length)
+                .take_while(|x| {
+                    self.memory[*x] == 0 && !self.stats.pc_cycles.contains_key(&(*x as u16))
+                })
+                .count();
+            let labelled: bool = self
+                .labels
+                .keys()
+                .any(|x| (address..address + idle_zeros).contains(&(*x as usize)));
+            if idle_zeros >= 4 && !labelled {
+                writeln!(
+                    text,
+                    "{:>10}{:>8}{:>10}  ...     {} zero words",
+                    "", "", "", idle_zeros
+                )
+                .unwrap();
+                address += idle_zeros;
+                continue;
+            }
+            let pc: u16 = address as u16;
+            for label in self.labels.get(&pc).into_iter().flatten() {
+                writeln!(text, "{}:", label).unwrap();
+            }
+            let word: u16 = self.memory[address];
+            match self.stats.pc_cycles.get(&pc) {
+                Some(cycles) => writeln!(
+                    text,
+                    "{:>10}{:>7.1}%{:>10}  {:#06X}: {:#06X}  {}",
+                    cycles,
+                    self.percentage(*cycles),
+                    self.stats.pc_histogram.get(&pc).copied().unwrap_or(0),
+                    pc,
+                    word,
+                    disassemble(word)
+                ),
+                None => writeln!(
+                    text,
+                    "{:>10}{:>8}{:>10}  {:#06X}: {:#06X}  {}",
+                    "",
+                    "",
+                    "",
+                    pc,
+                    word,
+                    disassemble(word)
+                ),
+            }
+            .unwrap();
+            address += 1;
+        }
+        text
+    }
+}
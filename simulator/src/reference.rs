@@ -0,0 +1,82 @@
+use crate::error::SimError;
+use crate::processor::Processor;
+use crate::types::{InstructionToken, Opcode};
+
+/// Opcodes the processor intentionally executes differently from this model.
+///
+/// The processor's sra fills every bit above the highest set bit of the shifted word with 1,
+/// whatever the sign of the register, and instruction_tests pins that behaviour. The model
+/// shifts in copies of the sign bit as the ISA specifies, so differential tests stop comparing
+/// when they reach one of these
+pub const DIVERGENT_OPCODES: [Opcode; 1] = [Opcode::ShiftRightArithmetic];
+
+/// Architectural model of the AYU ISA, executing whole instructions directly on registers and
+/// memory with none of the processor's state machine, ALU or pipeline registers
+///
+/// Used as the definition of what each instruction should do, to check the processor against
+pub struct Reference {
+    pub registers: [u16; 16],
+    pub memory: Vec<u16>,
+}
+
+impl Reference {
+    pub fn new(registers: [u16; 16], memory: &[u16]) -> Reference {
+        let mut reference = Reference {
+            registers,
+            memory: vec![0; 65536],
+        };
+        reference.memory[..memory.len()].copy_from_slice(memory);
+        reference
+    }
+
+    /// Execute the instruction at the PC, returns the exit code if it halted the program.
+    /// Console and interrupt instructions are not modelled and return InvalidSpecial
+    pub fn step(&mut self) -> Result<Option<u8>, SimError> {
+        let pc: u16 = self.registers[1];
+        let instruction: u16 = self.memory[pc as usize];
+        let token: InstructionToken = Processor::decode_instruction(instruction);
+        let (d, a, b) = (
+            token.nibble_2 as usize,
+            token.nibble_3 as usize,
+            token.nibble_4 as usize,
+        );
+        let byte: u16 = instruction & 0xFF;
+        if token.opcode == Opcode::Invalid {
+            return Err(SimError::InvalidOpcode { pc, instruction });
+        }
+        // the PC is incremented before any registers are read
+        self.registers[1] = pc.wrapping_add(1);
+        let (ra, rb) = (self.registers[a], self.registers[b]);
+        match token.opcode {
+            Opcode::Add => self.registers[d] = ra.wrapping_add(rb),
+            Opcode::Subtract => self.registers[d] = ra.wrapping_sub(rb),
+            Opcode::And => self.registers[d] = ra & rb,
+            Opcode::Or => self.registers[d] = ra | rb,
+            // words are compared unsigned
+            Opcode::SetIfLess => self.registers[d] = (ra < rb) as u16,
+            Opcode::SetIfEqual => self.registers[d] = (ra == rb) as u16,
+            Opcode::ShiftLeft => self.registers[d] = ra.checked_shl(rb as u32).unwrap_or(0),
+            Opcode::ShiftRightLogical => self.registers[d] = ra.checked_shr(rb as u32).unwrap_or(0),
+            // shifts of 15 or more leave only copies of the sign bit
+            Opcode::ShiftRightArithmetic => self.registers[d] = ((ra as i16) >> rb.min(15)) as u16,
+            Opcode::SetLower => self.registers[d] = byte,
+            Opcode::SetUpper => self.registers[d] |= byte << 8,
+            // nibble 2 is the offset and nibble 3 the data register for memory instructions
+            Opcode::LoadWord => {
+                self.registers[a] = self.memory[(d as u16).wrapping_add(rb) as usize]
+            }
+            Opcode::SaveWord => self.memory[(d as u16).wrapping_add(rb) as usize] = ra,
+            Opcode::SetPcIf => {
+                if ra == rb {
+                    self.registers[1] = d as u16;
+                }
+            }
+            Opcode::Special => match d {
+                1 => return Ok(Some(byte as u8)),
+                _ => return Err(SimError::InvalidSpecial { pc, instruction }),
+            },
+            Opcode::Invalid => unreachable!("invalid opcodes are rejected before execution"),
+        }
+        Ok(None)
+    }
+}
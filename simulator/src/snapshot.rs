@@ -0,0 +1,196 @@
+use std::fmt::Write;
+
+use crate::coredump::parse_word;
+use crate::error::SimError;
+use crate::types::{Opcode, PipelineRegisters, State};
+
+/// Version written at the top of every snapshot file, bumped whenever the format changes
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+/// Every part of a processor's state, enough to resume execution part way through an instruction
+///
+/// Control signals are not stored as they are derived from the state machine, and debugging
+/// aids (breakpoints, watchpoints and history) belong to the session rather than the processor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub clock_cycle: u64,
+    pub breakpoint: u64,
+    pub registers: [u16; 16],
+    pub memory: Vec<u16>,
+    pub instruction_register: u16,
+    pub instruction_address: u16,
+    /// The instruction the current instruction token was decoded from
+    pub decoded_instruction: u16,
+    pub state: State,
+    pub opcode: Opcode,
+    pub pipeline_registers: PipelineRegisters,
+    pub epc: u16,
+    pub interrupts_enabled: bool,
+    /// Clock cycle the instruction in progress was fetched on, so it's counted in the stats
+    /// once it finishes. None between instructions
+    pub fetch_cycle: Option<u64>,
+}
+
+impl Snapshot {
+    /// Write the snapshot in the versioned text format, only non-zero memory is included
+    pub fn serialize(&self) -> String {
+        let mut text = format!("AYU snapshot version {}\n", SNAPSHOT_VERSION);
+        let pipeline = &self.pipeline_registers;
+        let fields: [(&str, String); 17] = [
+            ("clock_cycle", self.clock_cycle.to_string()),
+            ("breakpoint", self.breakpoint.to_string()),
+            ("state", format!("{:?}", self.state)),
+            ("opcode", format!("{:#X}", self.opcode.to_u8())),
+            (
+                "instruction_register",
+                format!("{:#06X}", self.instruction_register),
+            ),
+            (
+                "instruction_address",
+                format!("{:#06X}", self.instruction_address),
+            ),
+            (
+                "decoded_instruction",
+                format!("{:#06X}", self.decoded_instruction),
+            ),
+            ("memory_data", format!("{:#06X}", pipeline.memory_data)),
+            (
+                "register_read_a",
+                format!("{:#06X}", pipeline.register_read_a),
+            ),
+            (
+                "register_read_b",
+                format!("{:#06X}", pipeline.register_read_b),
+            ),
+            ("alu_output", format!("{:#06X}", pipeline.alu_output)),
+            ("alu_negative", pipeline.alu_negative.to_string()),
+            ("alu_zero", pipeline.alu_zero.to_string()),
+            ("epc", format!("{:#06X}", self.epc)),
+            ("interrupts_enabled", self.interrupts_enabled.to_string()),
+            (
+                "fetch_cycle",
+                self.fetch_cycle
+                    .map_or(String::from("none"), |x| x.to_string()),
+            ),
+            ("memory_words", self.memory.len().to_string()),
+        ];
+        for (name, value) in fields {
+            writeln!(text, "{}: {}", name, value).unwrap();
+        }
+        for (i, register) in self.registers.iter().enumerate() {
+            writeln!(text, "R{:#02X}: {:#06X}", i, register).unwrap();
+        }
+        for (i, word) in self.memory.iter().enumerate() {
+            if *word != 0 {
+                writeln!(text, "M{:#06X}: {:#06X}", i, word).unwrap();
+            }
+        }
+        text
+    }
+
+    /// Read a snapshot written by serialize
+    pub fn parse(text: &str) -> Result<Snapshot, SimError> {
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header == format!("AYU snapshot version {}", SNAPSHOT_VERSION) => {
+            }
+            Some((_, header)) if header.starts_with("AYU snapshot version ") => {
+                return Err(SimError::parse(
+                    1,
+                    format!(
+                        "unsupported snapshot version '{}', expected {}",
+                        header.trim_start_matches("AYU snapshot version "),
+                        SNAPSHOT_VERSION
+                    ),
+                ))
+            }
+            _ => return Err(SimError::parse(1, "not an AYU snapshot")),
+        }
+        let mut snapshot = Snapshot {
+            clock_cycle: 0,
+            breakpoint: u64::MAX,
+            registers: [0; 16],
+            memory: vec![0; 65536],
+            instruction_register: 0,
+            instruction_address: 0,
+            decoded_instruction: 0,
+            state: State::PcRead,
+            opcode: Opcode::Invalid,
+            pipeline_registers: PipelineRegisters {
+                memory_data: 0,
+                register_read_a: 0,
+                register_read_b: 0,
+                alu_output: 0,
+                alu_negative: false,
+                alu_zero: false,
+            },
+            epc: 0,
+            interrupts_enabled: false,
+            fetch_cycle: None,
+        };
+        for (i, line) in lines {
+            let error = |message: &str| SimError::parse(i + 1, message);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (name, value) = line
+                .split_once(": ")
+                .ok_or_else(|| error("expected 'name: value'"))?;
+            let word = || parse_word(value).ok_or_else(|| error("invalid word"));
+            let boolean = || value.parse::<bool>().map_err(|_| error("invalid boolean"));
+            let pipeline = &mut snapshot.pipeline_registers;
+            match name {
+                "clock_cycle" => {
+                    snapshot.clock_cycle = value.parse().map_err(|_| error("invalid number"))?
+                }
+                "breakpoint" => {
+                    snapshot.breakpoint = value.parse().map_err(|_| error("invalid number"))?
+                }
+                "state" => {
+                    snapshot.state =
+                        State::from_name(value).ok_or_else(|| error("unknown state"))?
+                }
+                "opcode" => snapshot.opcode = Opcode::from_u8(word()? as u8),
+                "instruction_register" => snapshot.instruction_register = word()?,
+                "instruction_address" => snapshot.instruction_address = word()?,
+                "decoded_instruction" => snapshot.decoded_instruction = word()?,
+                "memory_data" => pipeline.memory_data = word()?,
+                "register_read_a" => pipeline.register_read_a = word()?,
+                "register_read_b" => pipeline.register_read_b = word()?,
+                "alu_output" => pipeline.alu_output = word()?,
+                "alu_negative" => pipeline.alu_negative = boolean()?,
+                "alu_zero" => pipeline.alu_zero = boolean()?,
+                "epc" => snapshot.epc = word()?,
+                "interrupts_enabled" => snapshot.interrupts_enabled = boolean()?,
+                "fetch_cycle" if value == "none" => snapshot.fetch_cycle = None,
+                "fetch_cycle" => {
+                    snapshot.fetch_cycle = Some(value.parse().map_err(|_| error("invalid number"))?)
+                }
+                "memory_words" if value == "65536" => (),
+                x if x.starts_with('R') => match parse_word(&x[1..]) {
+                    Some(register) if register < 16 => {
+                        snapshot.registers[register as usize] = word()?
+                    }
+                    _ => return Err(error("invalid register")),
+                },
+                x if x.starts_with('M') => match parse_word(&x[1..]) {
+                    Some(address) => snapshot.memory[address as usize] = word()?,
+                    None => return Err(error("invalid address")),
+                },
+                _ => return Err(error(&format!("unexpected field '{}'", name))),
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Write the snapshot to a file
+    pub fn save(&self, path: &str) -> Result<(), SimError> {
+        Ok(std::fs::write(path, self.serialize())?)
+    }
+
+    /// Read a snapshot from a file
+    pub fn load(path: &str) -> Result<Snapshot, SimError> {
+        let text = std::fs::read_to_string(path)?;
+        Snapshot::parse(&text)
+    }
+}
@@ -5,6 +5,7 @@ use crate::types::{
 use log::{error, info, trace};
 
 /// The state machine governing an AYU processor
+#[derive(Clone)]
 pub struct StateMachine {
     state: State,
     opcode: Opcode,
@@ -19,6 +20,21 @@ impl StateMachine {
         }
     }
 
+    /// Recreate a state machine part way through an instruction
+    pub fn from_parts(state: State, opcode: Opcode) -> StateMachine {
+        StateMachine { state, opcode }
+    }
+
+    /// The current state
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// The opcode of the instruction being executed, latched at decode
+    pub fn opcode(&self) -> &Opcode {
+        &self.opcode
+    }
+
     /// Returns the control signals for the current state
     pub fn get_control_signals(&mut self) -> ControlSignals {
         match self.state {
@@ -39,6 +55,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::PcRead => ControlSignals {
                 terminate: false,
@@ -57,6 +74,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::InstructionFetch => ControlSignals {
                 terminate: false,
@@ -75,6 +93,7 @@ impl StateMachine {
                 alu_source: AluSource::Constant1,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::Decode => ControlSignals {
                 terminate: false,
@@ -93,6 +112,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::SetLower => ControlSignals {
                 terminate: false,
@@ -111,6 +131,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::SetUpper => ControlSignals {
                 terminate: false,
@@ -129,6 +150,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::ArithmeticOperation => ControlSignals {
                 terminate: false,
@@ -143,10 +165,14 @@ impl StateMachine {
                 write_long: false,
                 read_pc: false,
                 write_pc: false,
-                alu_operation: AluOperation::from_opcode(&self.opcode),
+                alu_operation: AluOperation::from_opcode(&self.opcode).unwrap_or_else(|| {
+                    error!("Invalid opcode for ALU operation");
+                    AluOperation::Inactive
+                }),
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::SetIf => ControlSignals {
                 terminate: false,
@@ -165,6 +191,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::Memory => ControlSignals {
                 terminate: false,
@@ -183,6 +210,7 @@ impl StateMachine {
                 alu_source: AluSource::MemoryOffset,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::ArithmeticWriteBack => ControlSignals {
                 terminate: false,
@@ -201,6 +229,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::SetIfLess => ControlSignals {
                 terminate: false,
@@ -219,6 +248,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::SetIfEqual => ControlSignals {
                 terminate: false,
@@ -237,6 +267,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::MemoryRead => ControlSignals {
                 terminate: false,
@@ -255,6 +286,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::MemoryWrite => ControlSignals {
                 terminate: false,
@@ -273,6 +305,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::MemoryReadRegisterWriteback => ControlSignals {
                 terminate: false,
@@ -291,6 +324,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble3,
+                enter_interrupt: false,
             },
             State::SetPcTest => ControlSignals {
                 terminate: false,
@@ -309,6 +343,7 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::SetPcWriteback => ControlSignals {
                 terminate: false,
@@ -327,6 +362,45 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: false,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
+            },
+            State::InterruptSave => ControlSignals {
+                terminate: false,
+                decode: false,
+                address_source: AddressSource::InterruptVector,
+                memory_read: true,
+                memory_write: false,
+                instruction_register_write: false,
+                register_write: false,
+                register_write_source: RegisterWriteSource::Memory,
+                write_upper: false,
+                write_long: false,
+                read_pc: false,
+                write_pc: false,
+                alu_operation: AluOperation::Inactive,
+                alu_source: AluSource::Register,
+                process_special: false,
+                write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: true,
+            },
+            State::InterruptVector => ControlSignals {
+                terminate: false,
+                decode: false,
+                address_source: AddressSource::InterruptVector,
+                memory_read: false,
+                memory_write: false,
+                instruction_register_write: false,
+                register_write: false,
+                register_write_source: RegisterWriteSource::Memory,
+                write_upper: false,
+                write_long: false,
+                read_pc: false,
+                write_pc: true,
+                alu_operation: AluOperation::Inactive,
+                alu_source: AluSource::Register,
+                process_special: false,
+                write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
             State::Special => ControlSignals {
                 terminate: false,
@@ -345,18 +419,21 @@ impl StateMachine {
                 alu_source: AluSource::Register,
                 process_special: true,
                 write_register_target: RegisterWriteTarget::Nibble2,
+                enter_interrupt: false,
             },
         }
     }
 
     /// Transitions to another state based on the current state,
-    /// current instruction token, and alu result
+    /// current instruction token, alu result and interrupt line
     pub fn next_state(
         &mut self,
         instruction_token: &crate::types::InstructionToken,
         alu_zero: bool,
+        interrupt: bool,
     ) {
         match self.state {
+            State::PcRead if interrupt => self.state = State::InterruptSave,
             State::PcRead => self.state = State::InstructionFetch,
             State::InstructionFetch => self.state = State::Decode,
             State::Decode => self.decode(instruction_token),
@@ -394,6 +471,8 @@ impl StateMachine {
                 }
             }
             State::SetPcWriteback => self.state = State::PcRead,
+            State::InterruptSave => self.state = State::InterruptVector,
+            State::InterruptVector => self.state = State::PcRead,
             State::Terminate => {
                 info!("Program terminated, memory and registers dumped");
             }
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::types::{Opcode, State};
+
+/// Longest bar drawn in the PC histogram
+const HISTOGRAM_WIDTH: u64 = 40;
+
+/// Performance counters collected while a program runs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// Clock cycles run
+    pub cycles: u64,
+    /// Instructions that ran to completion
+    pub instructions: u64,
+    /// Instructions retired with each opcode, indexed by opcode value
+    pub opcode_instructions: [u64; 16],
+    /// Clock cycles from fetch to the end of each instruction, indexed by opcode value
+    pub opcode_cycles: [u64; 16],
+    pub branches_taken: u64,
+    pub branches_not_taken: u64,
+    pub instruction_fetches: u64,
+    /// Data reads, instruction fetches are not included
+    pub memory_reads: u64,
+    pub memory_writes: u64,
+    /// Clock cycles spent in each state, in the order of State::ALL. Only counted in cycle mode
+    /// as functional mode does not pass through the states
+    pub state_cycles: [u64; State::ALL.len()],
+    /// Number of instructions retired from each address
+    pub pc_histogram: BTreeMap<u16, u64>,
+    /// Clock cycles taken by the instructions retired from each address
+    pub pc_cycles: BTreeMap<u16, u64>,
+}
+
+impl Stats {
+    /// Average clock cycles per instruction, None before any instruction has retired
+    pub fn cpi(&self) -> Option<f64> {
+        match self.instructions {
+            0 => None,
+            x => Some(self.cycles as f64 / x as f64),
+        }
+    }
+
+    /// Number of instructions retired with an opcode
+    pub fn opcode_count(&self, opcode: &Opcode) -> u64 {
+        self.opcode_instructions[opcode.to_u8() as usize]
+    }
+
+    /// Average clock cycles taken by instructions with an opcode, None if there were none
+    pub fn opcode_cpi(&self, opcode: &Opcode) -> Option<f64> {
+        let index: usize = opcode.to_u8() as usize;
+        match self.opcode_instructions[index] {
+            0 => None,
+            x => Some(self.opcode_cycles[index] as f64 / x as f64),
+        }
+    }
+
+    /// Clock cycles spent in a state
+    pub fn state_count(&self, state: &State) -> u64 {
+        State::ALL
+            .iter()
+            .position(|x| x == state)
+            .map_or(0, |x| self.state_cycles[x])
+    }
+
+    pub(crate) fn record_cycle(&mut self, state: &State) {
+        self.cycles += 1;
+        if let Some(index) = State::ALL.iter().position(|x| x == state) {
+            self.state_cycles[index] += 1;
+        }
+    }
+
+    pub(crate) fn record_instruction(&mut self, opcode: &Opcode, pc: u16, cycles: u64) {
+        let index: usize = opcode.to_u8() as usize;
+        self.instructions += 1;
+        self.opcode_instructions[index] += 1;
+        self.opcode_cycles[index] += cycles;
+        *self.pc_histogram.entry(pc).or_insert(0) += 1;
+        *self.pc_cycles.entry(pc).or_insert(0) += cycles;
+    }
+}
+
+/// Format a CPI to 2 decimal places, or a dash if nothing ran
+fn format_cpi(cpi: Option<f64>) -> String {
+    cpi.map_or(String::from("-"), |x| format!("{:.2}", x))
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Clock cycles:         {}", self.cycles)?;
+        writeln!(f, "Instructions retired: {}", self.instructions)?;
+        writeln!(f, "CPI:                  {}", format_cpi(self.cpi()))?;
+        writeln!(
+            f,
+            "Branches:             {} taken, {} not taken",
+            self.branches_taken, self.branches_not_taken
+        )?;
+        writeln!(f, "Instruction fetches:  {}", self.instruction_fetches)?;
+        writeln!(f, "Memory reads:         {}", self.memory_reads)?;
+        writeln!(f, "Memory writes:        {}", self.memory_writes)?;
+        writeln!(
+            f,
+            "\n{:<10}{:>10}{:>10}{:>8}",
+            "Opcode", "Count", "Cycles", "CPI"
+        )?;
+        for value in 0..16 {
+            let opcode: Opcode = Opcode::from_u8(value);
+            let index: usize = value as usize;
+            if self.opcode_instructions[index] == 0 {
+                continue;
+            }
+            writeln!(
+                f,
+                "{:<10}{:>10}{:>10}{:>8}",
+                opcode.mnemonic(),
+                self.opcode_instructions[index],
+                self.opcode_cycles[index],
+                format_cpi(self.opcode_cpi(&opcode))
+            )?;
+        }
+        if self.state_cycles.iter().any(|x| *x != 0) {
+            writeln!(f, "\n{:<30}{:>10}", "State", "Cycles")?;
+            for (state, cycles) in State::ALL.iter().zip(self.state_cycles) {
+                if cycles != 0 {
+                    writeln!(f, "{:<30}{:>10}", format!("{:?}", state), cycles)?;
+                }
+            }
+        }
+        let most: u64 = self.pc_histogram.values().copied().max().unwrap_or(0);
+        writeln!(f, "\n{:<10}{:>10}", "PC", "Count")?;
+        for (pc, count) in &self.pc_histogram {
+            let bar: usize = (count * HISTOGRAM_WIDTH).div_ceil(most) as usize;
+            writeln!(f, "{:#06X}    {:>10}  {}", pc, count, "#".repeat(bar))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::disassembler::disassemble;
+use crate::error::SimError;
+
+/// Columns of a CSV trace, written as it's first line
+pub const CSV_HEADER: &str =
+    "cycle,pc,instruction,opcode,operands,register_writes,memory_reads,memory_writes";
+
+/// How trace records are written
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    /// One JSON object per line
+    JsonLines,
+    /// A header line followed by one row per instruction
+    Csv,
+}
+
+impl TraceFormat {
+    /// The format for a trace file, CSV for a .csv extension and JSON Lines for anything else
+    pub fn from_path(path: &str) -> TraceFormat {
+        match path.to_lowercase().ends_with(".csv") {
+            true => TraceFormat::Csv,
+            false => TraceFormat::JsonLines,
+        }
+    }
+}
+
+/// Everything a single retired instruction did
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceRecord {
+    /// Clock cycle the instruction was fetched on
+    pub cycle: u64,
+    pub pc: u16,
+    pub instruction: u16,
+    /// Register and value of every register write in order, including writes to the PC
+    pub register_writes: Vec<(u8, u16)>,
+    /// Address and value of every data read, instruction fetches are not included
+    pub memory_reads: Vec<(u16, u16)>,
+    /// Address and new value of every memory write
+    pub memory_writes: Vec<(u16, u16)>,
+}
+
+impl TraceRecord {
+    pub fn new(cycle: u64, pc: u16, instruction: u16) -> TraceRecord {
+        TraceRecord {
+            cycle,
+            pc,
+            instruction,
+            register_writes: Vec::new(),
+            memory_reads: Vec::new(),
+            memory_writes: Vec::new(),
+        }
+    }
+
+    /// The mnemonic and operands of the instruction as they are disassembled
+    pub fn decoded(&self) -> (String, Vec<String>) {
+        let text: String = disassemble(self.instruction);
+        match text.split_once(' ') {
+            Some((opcode, operands)) => (
+                opcode.to_string(),
+                operands.split(", ").map(String::from).collect(),
+            ),
+            None => (text, Vec::new()),
+        }
+    }
+
+    /// Format as a single line JSON object
+    pub fn to_json(&self) -> String {
+        let (opcode, operands) = self.decoded();
+        let operands: Vec<String> = operands.iter().map(|x| format!("\"{}\"", x)).collect();
+        let register_writes: Vec<String> = self
+            .register_writes
+            .iter()
+            .map(|(register, value)| {
+                format!("{{\"register\":{},\"value\":\"{:#06X}\"}}", register, value)
+            })
+            .collect();
+        format!(
+            "{{\"cycle\":{},\"pc\":\"{:#06X}\",\"instruction\":\"{:#06X}\",\"opcode\":\"{}\",\
+             \"operands\":[{}],\"register_writes\":[{}],\"memory_reads\":[{}],\"memory_writes\":[{}]}}",
+            self.cycle,
+            self.pc,
+            self.instruction,
+            opcode,
+            operands.join(","),
+            register_writes.join(","),
+            json_accesses(&self.memory_reads),
+            json_accesses(&self.memory_writes)
+        )
+    }
+
+    /// Format as a CSV row, lists of operands and accesses are separated by spaces
+    pub fn to_csv(&self) -> String {
+        let (opcode, operands) = self.decoded();
+        let register_writes: Vec<String> = self
+            .register_writes
+            .iter()
+            .map(|(register, value)| format!("r{}={:#06X}", register, value))
+            .collect();
+        format!(
+            "{},{:#06X},{:#06X},{},{},{},{},{}",
+            self.cycle,
+            self.pc,
+            self.instruction,
+            opcode,
+            operands.join(" "),
+            register_writes.join(" "),
+            csv_accesses(&self.memory_reads),
+            csv_accesses(&self.memory_writes)
+        )
+    }
+}
+
+fn json_accesses(accesses: &[(u16, u16)]) -> String {
+    accesses
+        .iter()
+        .map(|(address, value)| {
+            format!(
+                "{{\"address\":\"{:#06X}\",\"value\":\"{:#06X}\"}}",
+                address, value
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn csv_accesses(accesses: &[(u16, u16)]) -> String {
+    accesses
+        .iter()
+        .map(|(address, value)| format!("{:#06X}={:#06X}", address, value))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Writes a record of every retired instruction
+pub struct Trace {
+    format: TraceFormat,
+    writer: Box<dyn Write>,
+}
+
+impl Trace {
+    /// Trace to any writer, a CSV trace starts with it's header line
+    pub fn new(format: TraceFormat, mut writer: Box<dyn Write>) -> Result<Trace, SimError> {
+        if format == TraceFormat::Csv {
+            writeln!(writer, "{}", CSV_HEADER)?;
+        }
+        Ok(Trace { format, writer })
+    }
+
+    /// Trace to a file, in the format given by it's extension
+    pub fn create(path: &str) -> Result<Trace, SimError> {
+        let file = BufWriter::new(File::create(path)?);
+        Trace::new(TraceFormat::from_path(path), Box::new(file))
+    }
+
+    pub fn format(&self) -> TraceFormat {
+        self.format
+    }
+
+    /// Write a line for a retired instruction
+    pub fn record(&mut self, record: &TraceRecord) -> Result<(), SimError> {
+        let line: String = match self.format {
+            TraceFormat::JsonLines => record.to_json(),
+            TraceFormat::Csv => record.to_csv(),
+        };
+        writeln!(self.writer, "{}", line)?;
+        Ok(())
+    }
+
+    /// Write out anything still buffered
+    pub fn flush(&mut self) -> Result<(), SimError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
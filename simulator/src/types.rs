@@ -1,4 +1,4 @@
-use log::error;
+use std::fmt;
 
 /// Opcode representations
 #[derive(Clone, PartialEq, Debug)]
@@ -43,6 +43,172 @@ impl Opcode {
             _ => Opcode::Invalid,
         }
     }
+
+    /// Convert an opcode to the value of the first nibble of its instructions
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            Opcode::Add => 0x00,
+            Opcode::Subtract => 0x01,
+            Opcode::And => 0x02,
+            Opcode::Or => 0x03,
+            Opcode::SetIfLess => 0x04,
+            Opcode::SetIfEqual => 0x05,
+            Opcode::ShiftLeft => 0x06,
+            Opcode::ShiftRightLogical => 0x07,
+            Opcode::ShiftRightArithmetic => 0x08,
+            Opcode::SetLower => 0x09,
+            Opcode::SetUpper => 0x0A,
+            Opcode::LoadWord => 0x0B,
+            Opcode::SaveWord => 0x0C,
+            Opcode::SetPcIf => 0x0D,
+            Opcode::Invalid => 0x0E,
+            Opcode::Special => 0x0F,
+        }
+    }
+
+    /// Assembly mnemonic for an opcode
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Opcode::Add => "add",
+            Opcode::Subtract => "sub",
+            Opcode::And => "and",
+            Opcode::Or => "or",
+            Opcode::SetIfLess => "slt",
+            Opcode::SetIfEqual => "seq",
+            Opcode::ShiftLeft => "sll",
+            Opcode::ShiftRightLogical => "srl",
+            Opcode::ShiftRightArithmetic => "sra",
+            Opcode::SetLower => "setl",
+            Opcode::SetUpper => "setu",
+            Opcode::LoadWord => "lw",
+            Opcode::SaveWord => "sw",
+            Opcode::SetPcIf => "setpcif",
+            Opcode::Special => "special",
+            Opcode::Invalid => "invalid",
+        }
+    }
+
+    /// Convert an assembly mnemonic to it's opcode, special instructions such as `halt`
+    /// have their own mnemonics so are not included
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Opcode> {
+        match mnemonic.to_lowercase().as_str() {
+            "add" => Some(Opcode::Add),
+            "sub" => Some(Opcode::Subtract),
+            "and" => Some(Opcode::And),
+            "or" => Some(Opcode::Or),
+            "slt" => Some(Opcode::SetIfLess),
+            "seq" => Some(Opcode::SetIfEqual),
+            "sll" => Some(Opcode::ShiftLeft),
+            "srl" => Some(Opcode::ShiftRightLogical),
+            "sra" => Some(Opcode::ShiftRightArithmetic),
+            "setl" => Some(Opcode::SetLower),
+            "setu" => Some(Opcode::SetUpper),
+            "lw" => Some(Opcode::LoadWord),
+            "sw" => Some(Opcode::SaveWord),
+            "setpcif" => Some(Opcode::SetPcIf),
+            _ => None,
+        }
+    }
+}
+
+/// Operation of a special instruction, held in it's second nibble. Console instructions
+/// use the register in the third nibble, the others take no operands
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecialOperation {
+    /// Stop the program with the exit code in the low byte
+    Halt,
+    /// Write the low byte of a register as a character
+    PutChar,
+    /// Write a register as an unsigned decimal number
+    PutDecimal,
+    /// Write a register as 4 hex digits
+    PutHex,
+    /// Read a character into a register, 0xFFFF at the end of input
+    GetChar,
+    /// Read a line holding a decimal number into a register, 0xFFFF at the end of input
+    GetDecimal,
+    /// Return from an interrupt handler to the saved PC and enable interrupts again
+    ReturnFromInterrupt,
+    EnableInterrupts,
+    DisableInterrupts,
+    /// Save the image shown by the display to a numbered file
+    SaveFrame,
+}
+
+impl SpecialOperation {
+    pub fn from_u8(value: u8) -> Option<SpecialOperation> {
+        match value {
+            0x01 => Some(SpecialOperation::Halt),
+            0x02 => Some(SpecialOperation::PutChar),
+            0x03 => Some(SpecialOperation::PutDecimal),
+            0x04 => Some(SpecialOperation::PutHex),
+            0x05 => Some(SpecialOperation::GetChar),
+            0x06 => Some(SpecialOperation::GetDecimal),
+            0x07 => Some(SpecialOperation::ReturnFromInterrupt),
+            0x08 => Some(SpecialOperation::EnableInterrupts),
+            0x09 => Some(SpecialOperation::DisableInterrupts),
+            0x0A => Some(SpecialOperation::SaveFrame),
+            _ => None,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            SpecialOperation::Halt => 0x01,
+            SpecialOperation::PutChar => 0x02,
+            SpecialOperation::PutDecimal => 0x03,
+            SpecialOperation::PutHex => 0x04,
+            SpecialOperation::GetChar => 0x05,
+            SpecialOperation::GetDecimal => 0x06,
+            SpecialOperation::ReturnFromInterrupt => 0x07,
+            SpecialOperation::EnableInterrupts => 0x08,
+            SpecialOperation::DisableInterrupts => 0x09,
+            SpecialOperation::SaveFrame => 0x0A,
+        }
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            SpecialOperation::Halt => "halt",
+            SpecialOperation::PutChar => "putc",
+            SpecialOperation::PutDecimal => "putd",
+            SpecialOperation::PutHex => "puth",
+            SpecialOperation::GetChar => "getc",
+            SpecialOperation::GetDecimal => "getd",
+            SpecialOperation::ReturnFromInterrupt => "rfi",
+            SpecialOperation::EnableInterrupts => "ei",
+            SpecialOperation::DisableInterrupts => "di",
+            SpecialOperation::SaveFrame => "frame",
+        }
+    }
+
+    /// Whether the instruction names a register in it's third nibble
+    pub fn has_register(self) -> bool {
+        !matches!(
+            self,
+            SpecialOperation::Halt
+                | SpecialOperation::ReturnFromInterrupt
+                | SpecialOperation::EnableInterrupts
+                | SpecialOperation::DisableInterrupts
+                | SpecialOperation::SaveFrame
+        )
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<SpecialOperation> {
+        match mnemonic.to_lowercase().as_str() {
+            "halt" => Some(SpecialOperation::Halt),
+            "putc" => Some(SpecialOperation::PutChar),
+            "putd" => Some(SpecialOperation::PutDecimal),
+            "puth" => Some(SpecialOperation::PutHex),
+            "getc" => Some(SpecialOperation::GetChar),
+            "getd" => Some(SpecialOperation::GetDecimal),
+            "rfi" => Some(SpecialOperation::ReturnFromInterrupt),
+            "ei" => Some(SpecialOperation::EnableInterrupts),
+            "di" => Some(SpecialOperation::DisableInterrupts),
+            "frame" => Some(SpecialOperation::SaveFrame),
+            _ => None,
+        }
+    }
 }
 
 /// Type of instruction
@@ -80,7 +246,7 @@ impl InstructionType {
 }
 
 /// Current state of the FSM governing control signals
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum State {
     PcRead,
     InstructionFetch,
@@ -99,9 +265,44 @@ pub enum State {
     SetPcTest,
     SetPcWriteback,
     Special,
+    /// Save the PC and read the address of the interrupt handler
+    InterruptSave,
+    /// Jump to the interrupt handler
+    InterruptVector,
     Terminate,
 }
 
+impl State {
+    /// Every state, in the order they are declared
+    pub const ALL: [State; 20] = [
+        State::PcRead,
+        State::InstructionFetch,
+        State::Decode,
+        State::SetLower,
+        State::SetUpper,
+        State::ArithmeticOperation,
+        State::ArithmeticWriteBack,
+        State::SetIf,
+        State::SetIfLess,
+        State::SetIfEqual,
+        State::Memory,
+        State::MemoryRead,
+        State::MemoryReadRegisterWriteback,
+        State::MemoryWrite,
+        State::SetPcTest,
+        State::SetPcWriteback,
+        State::Special,
+        State::InterruptSave,
+        State::InterruptVector,
+        State::Terminate,
+    ];
+
+    /// Get a state from it's name, as printed by Debug
+    pub fn from_name(name: &str) -> Option<State> {
+        State::ALL.into_iter().find(|x| format!("{:?}", x) == name)
+    }
+}
+
 /// Instruction that has been broken into more useable pieces
 #[derive(Clone)]
 pub struct InstructionToken {
@@ -113,12 +314,16 @@ pub struct InstructionToken {
 }
 
 /// Memory access address source
+#[derive(Debug, Clone)]
 pub enum AddressSource {
     Alu,
     ProgramCounter,
+    /// The word holding the address of the interrupt handler
+    InterruptVector,
 }
 
 /// Where the value written to a register is taken from
+#[derive(Debug, Clone)]
 pub enum RegisterWriteSource {
     InstructionByte2,
     Memory,
@@ -142,27 +347,25 @@ pub enum AluOperation {
 }
 
 impl AluOperation {
-    /// Determine appropriate ALU operation based on Opcode
-    pub fn from_opcode(opcode: &Opcode) -> AluOperation {
+    /// Determine appropriate ALU operation based on Opcode, None for opcodes that don't use the ALU
+    pub fn from_opcode(opcode: &Opcode) -> Option<AluOperation> {
         match opcode {
-            Opcode::Add => AluOperation::Add,
-            Opcode::Subtract => AluOperation::Subtract,
-            Opcode::And => AluOperation::And,
-            Opcode::Or => AluOperation::Or,
-            Opcode::ShiftLeft => AluOperation::ShiftLeft,
-            Opcode::ShiftRightLogical => AluOperation::ShiftRightLogical,
-            Opcode::ShiftRightArithmetic => AluOperation::ShiftRightArithmetic,
-            Opcode::SetIfEqual => AluOperation::Subtract,
-            Opcode::SetIfLess => AluOperation::Subtract,
-            _ => {
-                error!("Invalid opcode for ALU operation");
-                panic!("Invalid opcode for ALU operation")
-            }
+            Opcode::Add => Some(AluOperation::Add),
+            Opcode::Subtract => Some(AluOperation::Subtract),
+            Opcode::And => Some(AluOperation::And),
+            Opcode::Or => Some(AluOperation::Or),
+            Opcode::ShiftLeft => Some(AluOperation::ShiftLeft),
+            Opcode::ShiftRightLogical => Some(AluOperation::ShiftRightLogical),
+            Opcode::ShiftRightArithmetic => Some(AluOperation::ShiftRightArithmetic),
+            Opcode::SetIfEqual => Some(AluOperation::Subtract),
+            Opcode::SetIfLess => Some(AluOperation::Subtract),
+            _ => None,
         }
     }
 }
 
 /// Where ALU input A is taken from
+#[derive(Debug, Clone)]
 pub enum AluSource {
     Register,
     Constant1,
@@ -170,18 +373,305 @@ pub enum AluSource {
 }
 
 /// Which nibble in the instruction is the target register to be written to
+#[derive(Debug, Clone)]
 pub enum RegisterWriteTarget {
     Nibble2,
     Nibble3,
 }
 
-/// Processor either runs or stops
-#[derive(PartialEq)]
+/// Comparison made by a conditional breakpoint
+#[derive(Debug, Clone, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// Operator symbols, longest first so `<=` is not mistaken for `<`
+    const SYMBOLS: [(&'static str, Comparison); 6] = [
+        ("==", Comparison::Equal),
+        ("!=", Comparison::NotEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+    ];
+
+    /// Compare 2 values
+    pub fn compare(&self, a: u16, b: u16) -> bool {
+        match self {
+            Comparison::Equal => a == b,
+            Comparison::NotEqual => a != b,
+            Comparison::Less => a < b,
+            Comparison::LessOrEqual => a <= b,
+            Comparison::Greater => a > b,
+            Comparison::GreaterOrEqual => a >= b,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        Comparison::SYMBOLS
+            .iter()
+            .find(|(_, comparison)| comparison == self)
+            .map(|(symbol, _)| *symbol)
+            .unwrap_or("?")
+    }
+}
+
+/// Condition checked at the start of every instruction that stops the processor when met
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breakpoint {
+    /// The program counter (R1) is at an address
+    Address(u16),
+    /// The next instruction has an opcode
+    Opcode(Opcode),
+    /// A register compares to a value
+    Register {
+        register: u8,
+        comparison: Comparison,
+        value: u16,
+    },
+}
+
+impl Breakpoint {
+    /// Parse a breakpoint from an address (`0x0004`), a mnemonic (`lw`)
+    /// or a register condition (`r3==0x10`)
+    pub fn from_string(text: &str) -> Option<Breakpoint> {
+        let text: String = text.replace(' ', "");
+        if let Some(address) = parse_word_number(&text) {
+            return Some(Breakpoint::Address(address));
+        }
+        if text.eq_ignore_ascii_case("special") {
+            return Some(Breakpoint::Opcode(Opcode::Special));
+        }
+        if let Some(opcode) = Opcode::from_mnemonic(&text) {
+            return Some(Breakpoint::Opcode(opcode));
+        }
+        let (symbol, comparison) = Comparison::SYMBOLS
+            .into_iter()
+            .find(|(symbol, _)| text.contains(symbol))?;
+        let (register, value) = text.split_once(symbol)?;
+        let register: u8 = match register.to_lowercase().as_str() {
+            "pc" => 1,
+            x => x.strip_prefix('r')?.parse::<u8>().ok()?,
+        };
+        if register > 15 {
+            return None;
+        }
+        Some(Breakpoint::Register {
+            register,
+            comparison,
+            value: parse_word_number(value)?,
+        })
+    }
+
+    /// Whether the breakpoint is met before executing an instruction
+    pub fn is_met(&self, registers: &[u16; 16], instruction: u16) -> bool {
+        match self {
+            Breakpoint::Address(address) => registers[1] == *address,
+            Breakpoint::Opcode(opcode) => Opcode::from_u8((instruction >> 12) as u8) == *opcode,
+            Breakpoint::Register {
+                register,
+                comparison,
+                value,
+            } => comparison.compare(registers[*register as usize], *value),
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Address(address) => write!(f, "pc == {:#06X}", address),
+            Breakpoint::Opcode(opcode) => write!(f, "opcode {}", opcode.mnemonic()),
+            Breakpoint::Register {
+                register,
+                comparison,
+                value,
+            } => write!(f, "r{} {} {:#06X}", register, comparison.symbol(), value),
+        }
+    }
+}
+
+/// Kind of memory access that triggers a watchpoint
+#[derive(Debug, Clone, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+/// Memory address or register that stops the processor when it is accessed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Watchpoint {
+    Memory { address: u16, access: Access },
+    Register(u8),
+}
+
+impl Watchpoint {
+    /// Parse a watchpoint from a register (`r3`) or a memory address with an optional
+    /// access kind (`0x10` or `0x10:w` for writes, `0x10:r` for reads, `0x10:rw` for both)
+    pub fn from_string(text: &str) -> Option<Watchpoint> {
+        let text: String = text.replace(' ', "").to_lowercase();
+        if let Some(register) = text.strip_prefix('r').and_then(|x| x.parse::<u8>().ok()) {
+            return match register < 16 {
+                true => Some(Watchpoint::Register(register)),
+                false => None,
+            };
+        }
+        if text == "pc" {
+            return Some(Watchpoint::Register(1));
+        }
+        let (address, access) = match text.split_once(':') {
+            Some((address, "r")) => (address, Access::Read),
+            Some((address, "w")) => (address, Access::Write),
+            Some((address, "rw")) => (address, Access::ReadWrite),
+            Some(_) => return None,
+            None => (text.as_str(), Access::Write),
+        };
+        Some(Watchpoint::Memory {
+            address: parse_word_number(address)?,
+            access,
+        })
+    }
+
+    /// Whether a read or write of memory at an address triggers the watchpoint
+    pub fn watches_memory(&self, address: u16, write: bool) -> bool {
+        match self {
+            Watchpoint::Memory {
+                address: watched,
+                access,
+            } => {
+                *watched == address
+                    && match access {
+                        Access::Read => !write,
+                        Access::Write => write,
+                        Access::ReadWrite => true,
+                    }
+            }
+            Watchpoint::Register(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Watchpoint::Memory { address, access } => {
+                write!(f, "M{:#06X} ({:?})", address, access)
+            }
+            Watchpoint::Register(register) => write!(f, "r{}", register),
+        }
+    }
+}
+
+/// An access that triggered a watchpoint, for reads the old and new values are the value read
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchpointHit {
+    pub watchpoint: Watchpoint,
+    pub pc: u16,
+    pub instruction: u16,
+    pub old_value: u16,
+    pub new_value: u16,
+}
+
+/// Parse a decimal or 0x prefixed hex number, the syntax breakpoints, watchpoints and
+/// debugger commands share
+pub(crate) fn parse_number(text: &str) -> Option<u32> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse::<u32>().ok(),
+    }
+}
+
+/// Parse a number that has to fit in a word
+fn parse_word_number(text: &str) -> Option<u16> {
+    u16::try_from(parse_number(text)?).ok()
+}
+
+/// Processor either runs or stops, with the reason it stopped
+#[derive(Debug, Clone, PartialEq)]
 pub enum RunState {
-    Stop,
+    Stop(StopReason),
     Continue,
 }
 
+/// How the processor executes instructions
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ExecutionMode {
+    /// Every clock cycle of the multicycle state machine is modelled
+    #[default]
+    Cycle,
+    /// Each call to run executes a whole instruction, counting the clock cycles the state
+    /// machine would take. Breakpoints and watchpoints are only checked between instructions
+    Functional,
+}
+
+/// Outcome of running a processor for more than one clock cycle
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunSummary {
+    /// Stop if the processor stopped, Continue if the run ended with the processor still running
+    pub state: RunState,
+    /// Clock cycles the clock advanced by, a stop part way through a cycle leaves the clock
+    /// on that cycle
+    pub cycles: u64,
+    /// Instructions completed, counted each time the state machine returns to PcRead
+    pub instructions: u64,
+}
+
+/// Why the processor stopped, and the address and word of the instruction it stopped at
+#[derive(Debug, Clone, PartialEq)]
+pub struct StopReason {
+    pub cause: StopCause,
+    pub pc: u16,
+    pub instruction: u16,
+}
+
+/// What stopped the processor
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopCause {
+    /// A halt instruction was executed, with the exit code it supplied
+    Halt(u8),
+    /// The state machine terminated after an invalid instruction
+    Terminated,
+    /// The clock cycle breakpoint was passed
+    ClockCycle,
+    /// A breakpoint was met before the next instruction
+    Breakpoint(Breakpoint),
+    /// A watched register or memory address was accessed
+    Watchpoint(WatchpointHit),
+}
+
+impl StopReason {
+    /// Exit status for the simulator, the program's exit code if it halted
+    pub fn exit_code(&self) -> u8 {
+        match self.cause {
+            StopCause::Halt(exit_code) => exit_code,
+            StopCause::Terminated => 1,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.cause {
+            StopCause::Halt(exit_code) => write!(f, "halted with exit code {}", exit_code)?,
+            StopCause::Terminated => write!(f, "terminated")?,
+            StopCause::ClockCycle => write!(f, "reached the clock cycle breakpoint")?,
+            StopCause::Breakpoint(breakpoint) => {
+                write!(f, "reached breakpoint when {}", breakpoint)?
+            }
+            StopCause::Watchpoint(hit) => write!(f, "hit watchpoint {}", hit.watchpoint)?,
+        }
+        write!(f, " at {:#06X} ({:#06X})", self.pc, self.instruction)
+    }
+}
+
 /// Results of an ALU operation
 pub struct AluOutput {
     pub result: u16,
@@ -190,6 +680,7 @@ pub struct AluOutput {
 }
 
 /// Values of all intermediate pipeline registers
+#[derive(Debug, Clone, PartialEq)]
 pub struct PipelineRegisters {
     pub memory_data: u16,
     pub register_read_a: u16,
@@ -200,6 +691,7 @@ pub struct PipelineRegisters {
 }
 
 /// Values for all control signals
+#[derive(Debug, Clone)]
 pub struct ControlSignals {
     pub terminate: bool,
     pub decode: bool,
@@ -217,4 +709,6 @@ pub struct ControlSignals {
     pub alu_source: AluSource,
     pub process_special: bool,
     pub write_register_target: RegisterWriteTarget,
+    /// Save the PC read into the pipeline to the EPC register and disable interrupts
+    pub enter_interrupt: bool,
 }
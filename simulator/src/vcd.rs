@@ -0,0 +1,284 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::error::SimError;
+use crate::processor::Processor;
+
+/// A signal value as it is written to a VCD file
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Bit(bool),
+    Word(u16),
+    /// Enumerations such as the FSM state are shown as text
+    Text(String),
+}
+
+impl Value {
+    fn format(&self, id: &str) -> String {
+        match self {
+            Value::Bit(bit) => format!("{}{}", *bit as u8, id),
+            Value::Word(word) => format!("b{:b} {}", word, id),
+            Value::Text(text) => format!("s{} {}", text, id),
+        }
+    }
+
+    fn declaration(&self) -> &'static str {
+        match self {
+            Value::Bit(_) => "wire 1",
+            Value::Word(_) => "wire 16",
+            Value::Text(_) => "string 1",
+        }
+    }
+}
+
+/// A signal in the dump, in the scope it is grouped under
+struct Signal {
+    scope: &'static str,
+    name: &'static str,
+    value: fn(&Processor) -> Value,
+}
+
+/// Everything dumped each clock cycle, the clock itself is added separately
+const SIGNALS: [Signal; 28] = [
+    Signal {
+        scope: "processor",
+        name: "state",
+        value: |x| Value::Text(format!("{:?}", x.state())),
+    },
+    Signal {
+        scope: "processor",
+        name: "pc",
+        value: |x| Value::Word(x.registers()[1]),
+    },
+    Signal {
+        scope: "processor",
+        name: "instruction_register",
+        value: |x| Value::Word(x.instruction_register()),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "terminate",
+        value: |x| Value::Bit(x.control_signals().terminate),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "decode",
+        value: |x| Value::Bit(x.control_signals().decode),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "address_source",
+        value: |x| Value::Text(format!("{:?}", x.control_signals().address_source)),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "memory_read",
+        value: |x| Value::Bit(x.control_signals().memory_read),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "memory_write",
+        value: |x| Value::Bit(x.control_signals().memory_write),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "instruction_register_write",
+        value: |x| Value::Bit(x.control_signals().instruction_register_write),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "register_write",
+        value: |x| Value::Bit(x.control_signals().register_write),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "register_write_source",
+        value: |x| Value::Text(format!("{:?}", x.control_signals().register_write_source)),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "write_upper",
+        value: |x| Value::Bit(x.control_signals().write_upper),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "write_long",
+        value: |x| Value::Bit(x.control_signals().write_long),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "read_pc",
+        value: |x| Value::Bit(x.control_signals().read_pc),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "write_pc",
+        value: |x| Value::Bit(x.control_signals().write_pc),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "alu_operation",
+        value: |x| Value::Text(format!("{:?}", x.control_signals().alu_operation)),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "alu_source",
+        value: |x| Value::Text(format!("{:?}", x.control_signals().alu_source)),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "process_special",
+        value: |x| Value::Bit(x.control_signals().process_special),
+    },
+    Signal {
+        scope: "control_signals",
+        name: "write_register_target",
+        value: |x| Value::Text(format!("{:?}", x.control_signals().write_register_target)),
+    },
+    Signal {
+        scope: "pipeline_registers",
+        name: "memory_data",
+        value: |x| Value::Word(x.pipeline_registers().memory_data),
+    },
+    Signal {
+        scope: "pipeline_registers",
+        name: "register_read_a",
+        value: |x| Value::Word(x.pipeline_registers().register_read_a),
+    },
+    Signal {
+        scope: "pipeline_registers",
+        name: "register_read_b",
+        value: |x| Value::Word(x.pipeline_registers().register_read_b),
+    },
+    Signal {
+        scope: "pipeline_registers",
+        name: "alu_output",
+        value: |x| Value::Word(x.pipeline_registers().alu_output),
+    },
+    Signal {
+        scope: "pipeline_registers",
+        name: "alu_negative",
+        value: |x| Value::Bit(x.pipeline_registers().alu_negative),
+    },
+    Signal {
+        scope: "pipeline_registers",
+        name: "alu_zero",
+        value: |x| Value::Bit(x.pipeline_registers().alu_zero),
+    },
+    Signal {
+        scope: "interrupts",
+        name: "enter_interrupt",
+        value: |x| Value::Bit(x.control_signals().enter_interrupt),
+    },
+    Signal {
+        scope: "interrupts",
+        name: "interrupts_enabled",
+        value: |x| Value::Bit(x.interrupts_enabled()),
+    },
+    Signal {
+        scope: "interrupts",
+        name: "epc",
+        value: |x| Value::Word(x.epc()),
+    },
+];
+
+/// Identifier of the clock, signals are numbered after it
+const CLOCK_ID: &str = "!";
+
+/// Short identifier used for a signal in value changes, printable characters after the clock's
+fn signal_id(index: usize) -> String {
+    ((b'"' + index as u8) as char).to_string()
+}
+
+/// Writes the FSM state, control signals and pipeline registers of every clock cycle as a
+/// Value Change Dump, each cycle is 2 time units with the clock high for the first
+pub struct VcdWriter {
+    writer: Box<dyn Write>,
+    /// Values written for the last cycle, empty until the definitions have been written
+    previous: Vec<Value>,
+    last_cycle: Option<u64>,
+}
+
+impl VcdWriter {
+    pub fn new(writer: Box<dyn Write>) -> VcdWriter {
+        VcdWriter {
+            writer,
+            previous: Vec::new(),
+            last_cycle: None,
+        }
+    }
+
+    /// Dump to a file
+    pub fn create(path: &str) -> Result<VcdWriter, SimError> {
+        let file = BufWriter::new(File::create(path)?);
+        Ok(VcdWriter::new(Box::new(file)))
+    }
+
+    /// Record the values at the end of a clock cycle, only signals that changed are written.
+    /// Time can't go backwards in a dump, so cycles that are run again after stepping back
+    /// are skipped
+    pub fn sample(&mut self, clock_cycle: u64, processor: &Processor) -> Result<(), SimError> {
+        if self.last_cycle.is_some_and(|x| clock_cycle <= x) {
+            return Ok(());
+        }
+        self.last_cycle = Some(clock_cycle);
+        let values: Vec<Value> = SIGNALS.iter().map(|x| (x.value)(processor)).collect();
+        if self.previous.is_empty() {
+            self.write_definitions(&values)?;
+            writeln!(self.writer, "#{}", clock_cycle * 2)?;
+            writeln!(self.writer, "$dumpvars")?;
+            writeln!(self.writer, "1{}", CLOCK_ID)?;
+            for (i, value) in values.iter().enumerate() {
+                writeln!(self.writer, "{}", value.format(&signal_id(i)))?;
+            }
+            writeln!(self.writer, "$end")?;
+        } else {
+            writeln!(self.writer, "#{}", clock_cycle * 2)?;
+            writeln!(self.writer, "1{}", CLOCK_ID)?;
+            for (i, value) in values.iter().enumerate() {
+                if *value != self.previous[i] {
+                    writeln!(self.writer, "{}", value.format(&signal_id(i)))?;
+                }
+            }
+        }
+        writeln!(self.writer, "#{}", clock_cycle * 2 + 1)?;
+        writeln!(self.writer, "0{}", CLOCK_ID)?;
+        self.previous = values;
+        Ok(())
+    }
+
+    /// The header declaring every signal, grouped into a scope per struct
+    fn write_definitions(&mut self, values: &[Value]) -> Result<(), SimError> {
+        writeln!(self.writer, "$version AYU simulator $end")?;
+        writeln!(self.writer, "$timescale 1ns $end")?;
+        writeln!(self.writer, "$scope module ayu $end")?;
+        writeln!(self.writer, "$var wire 1 {} clock $end", CLOCK_ID)?;
+        let mut scope: &str = "";
+        for (i, (signal, value)) in SIGNALS.iter().zip(values).enumerate() {
+            if signal.scope != scope {
+                if !scope.is_empty() {
+                    writeln!(self.writer, "$upscope $end")?;
+                }
+                scope = signal.scope;
+                writeln!(self.writer, "$scope module {} $end", scope)?;
+            }
+            writeln!(
+                self.writer,
+                "$var {} {} {} $end",
+                value.declaration(),
+                signal_id(i),
+                signal.name
+            )?;
+        }
+        writeln!(self.writer, "$upscope $end")?;
+        writeln!(self.writer, "$upscope $end")?;
+        writeln!(self.writer, "$enddefinitions $end")?;
+        Ok(())
+    }
+
+    /// Write out anything still buffered
+    pub fn flush(&mut self) -> Result<(), SimError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
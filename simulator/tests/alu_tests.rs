@@ -0,0 +1,46 @@
+use simulator::{processor::Processor, types::RunState};
+
+/// Negating 0 to subtract it used to overflow
+#[test]
+fn subtract_zero() {
+    let mut register_state = [0; 16];
+    register_state[2] = 0x1234;
+    let mut memory_state = [0; 65536];
+    // sub r3, r2, r0
+    memory_state[0] = 0x1320;
+    memory_state[1] = 0xF100;
+    let mut processor = Processor::new_from_array(register_state, memory_state, false);
+    let mut running = RunState::Continue;
+    while running == RunState::Continue {
+        running = processor.run().unwrap();
+    }
+    let (registers, _memory) = processor.coredump(false).unwrap();
+    assert_eq!(registers[3], 0x1234);
+}
+
+/// Set if less compares the registers as unsigned words
+#[test]
+fn set_if_less_is_unsigned() {
+    for (a, b, expected) in [
+        (1, 2, 1),
+        (2, 1, 0),
+        (5, 5, 0),
+        (1, 0xFFFF, 1),
+        (0xFFFF, 1, 0),
+    ] {
+        let mut register_state = [0; 16];
+        register_state[2] = a;
+        register_state[4] = b;
+        let mut memory_state = [0; 65536];
+        // slt r3, r2, r4
+        memory_state[0] = 0x4324;
+        memory_state[1] = 0xF100;
+        let mut processor = Processor::new_from_array(register_state, memory_state, false);
+        let mut running = RunState::Continue;
+        while running == RunState::Continue {
+            running = processor.run().unwrap();
+        }
+        let (registers, _memory) = processor.coredump(false).unwrap();
+        assert_eq!(registers[3], expected, "{:#06X} < {:#06X}", a, b);
+    }
+}
@@ -0,0 +1,173 @@
+use simulator::{
+    assembler::assemble,
+    disassembler::{disassemble, disassemble_memory},
+    error::SimError,
+    processor::Processor,
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+#[test]
+fn mnemonics_match_hand_assembled() {
+    let source = "
+        setl r3, 0xF5  # set r3 to 245
+        setl r4, 0x1E
+        setu r4, 0x19  # set r4 to 6430 / 0x191E
+        add r5, r3, r4 # set r5 to r3 + r4
+        halt
+    ";
+    assert_eq!(
+        assemble(source).unwrap(),
+        vec![0x93F5, 0x941E, 0xA419, 0x0534, 0xF100]
+    );
+}
+
+#[test]
+fn every_mnemonic() {
+    let source = "
+        add r1, r2, r3
+        sub r1, r2, r3
+        and r1, r2, r3
+        or r1, r2, r3
+        slt r1, r2, r3
+        seq r1, r2, r3
+        sll r1, r2, r3
+        srl r1, r2, r3
+        sra r1, r2, r3
+        setl r15, 255
+        setu pc, 0b1010
+        lw r3, 1(r2)
+        sw r2, (r3)
+        setpcif 7, r2, r3
+        halt
+        .word 0x1234, 42
+    ";
+    assert_eq!(
+        assemble(source).unwrap(),
+        vec![
+            0x0123, 0x1123, 0x2123, 0x3123, 0x4123, 0x5123, 0x6123, 0x7123, 0x8123, 0x9FFF, 0xA10A,
+            0xB132, 0xC023, 0xD723, 0xF100, 0x1234, 0x002A
+        ]
+    );
+}
+
+#[test]
+fn raw_hex_lines_still_assemble() {
+    let source = std::fs::read_to_string("../example_bytecode/basic_addition.ayu").unwrap();
+    assert_eq!(
+        assemble(&source).unwrap(),
+        vec![0x93F5, 0x941E, 0xA419, 0x0534, 0xF100]
+    );
+}
+
+#[test]
+fn labels_and_forward_references() {
+    let source = "
+    start:  setl r2, data
+            setpcif end, r0, r0
+    loop:   setpcif loop, r0, r0
+    end:    halt
+    data:   .word 0xBEEF
+    ";
+    assert_eq!(
+        assemble(source).unwrap(),
+        vec![0x9204, 0xD300, 0xD200, 0xF100, 0xBEEF]
+    );
+}
+
+/// The line, column and message of a parse error from assembling some source
+fn parse_error(source: &str) -> (usize, usize, String) {
+    match assemble(source).unwrap_err() {
+        SimError::Parse {
+            line,
+            column,
+            message,
+        } => (line, column, message),
+        error => panic!("expected a parse error, got {}", error),
+    }
+}
+
+#[test]
+fn errors_report_line() {
+    let (line, column, message) = parse_error("setl r2, 1\nmul r2, r2, r2\n");
+    assert_eq!((line, column), (2, 1));
+    assert!(message.contains("mul"));
+
+    let (line, _, _) = parse_error("add r2, r2\n");
+    assert_eq!(line, 1);
+
+    let (_, column, message) = parse_error("setpcif missing, r0, r0\n");
+    assert_eq!(column, 9);
+    assert!(message.contains("missing"));
+
+    let (line, _, _) = parse_error("a: halt\na: halt\n");
+    assert_eq!(line, 2);
+
+    let (_, column, _) = parse_error("loop:  add r2, r2, r16\n");
+    assert_eq!(column, 20);
+}
+
+#[test]
+fn branch_target_out_of_range() {
+    let mut source = String::from("setpcif far, r0, r0\n");
+    for _ in 0..16 {
+        source.push_str("halt\n");
+    }
+    source.push_str("far: halt\n");
+    assert_eq!(parse_error(&source).0, 1);
+}
+
+#[test]
+fn program_too_large() {
+    let source: String = ".word 0\n".repeat(65537);
+    assert!(matches!(
+        assemble(&source),
+        Err(SimError::ProgramTooLarge { words: 65537 })
+    ));
+}
+
+#[test]
+fn assembled_program_runs() {
+    let mut processor = Processor::new_from_file(
+        String::from("../example_bytecode/sum_countdown.ayu"),
+        u64::MAX,
+        false,
+    )
+    .unwrap();
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    let (dump_registers, _dump_memory) = processor.coredump(false).unwrap();
+    assert_eq!(dump_registers[4], 15);
+    assert_eq!(dump_registers[3], 0);
+}
+
+#[test]
+fn disassembly_round_trips() {
+    for word in 0..=u16::MAX {
+        let text = disassemble(word);
+        assert_eq!(assemble(&text).unwrap(), vec![word], "{}", text);
+    }
+}
+
+#[test]
+fn disassembly_listing() {
+    assert_eq!(disassemble(0x0534), "add r5, r3, r4");
+    assert_eq!(disassemble(0x93F5), "setl r3, 0xF5");
+    assert_eq!(disassemble(0xB122), "lw r2, 1(r2)");
+    assert_eq!(disassemble(0xF100), "halt");
+    assert_eq!(disassemble(0xF103), "halt 0x03");
+    assert_eq!(assemble("halt 3").unwrap(), vec![0xF103]);
+    let mut memory = vec![0x93F5, 0xF100];
+    memory.extend([0; 10]);
+    memory.push(0x1234);
+    memory.extend([0; 100]);
+    assert_eq!(
+        disassemble_memory(&memory),
+        vec![
+            "0x0000: 0x93F5  setl r3, 0xF5",
+            "0x0001: 0xF100  halt",
+            "...     10 zero words",
+            "0x000C: 0x1234  sub r2, r3, r4",
+        ]
+    );
+}
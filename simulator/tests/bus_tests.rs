@@ -0,0 +1,104 @@
+use simulator::{
+    assembler::assemble,
+    devices::{Ram, Rom, Timer, TIMER_BASE, TIMER_RELOAD},
+    error::SimError,
+    processor::Processor,
+    types::ExecutionMode,
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+/// Copies a word from 0x4000 to 0x4001 and 0x8000
+const COPY: &str = "
+        setu r2, 0x40
+        setu r3, 0x80
+        lw r4, 0(r2)
+        sw r4, 1(r2)
+        sw r4, 0(r3)
+        halt
+";
+
+fn memory(source: &str) -> [u16; 65536] {
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    memory
+}
+
+#[test]
+fn devices_replace_ram() {
+    for mode in [ExecutionMode::Cycle, ExecutionMode::Functional] {
+        let mut memory = memory(COPY);
+        memory[0x4000] = 0x1111;
+        let mut processor = Processor::new_from_array([0; 16], memory, false)
+            .with_execution_mode(mode)
+            .with_device(0x4000, Box::new(Rom::new(vec![0x1234])))
+            .unwrap()
+            .with_device(0x4001, Box::new(Ram::new(16)))
+            .unwrap();
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        // the ROM is read instead of the RAM under it
+        assert_eq!(processor.registers()[4], 0x1234);
+        assert_eq!(processor.peek(0x4001), 0x1234);
+        assert_eq!(processor.memory()[0x4001], 0);
+        // addresses without a device are still RAM
+        assert_eq!(processor.memory()[0x8000], 0x1234);
+        assert_eq!(processor.peek(0x8000), 0x1234);
+    }
+}
+
+#[test]
+fn rom_ignores_writes() {
+    let mut processor = Processor::new_from_array([0; 16], memory(COPY), false)
+        .with_device(0x4000, Box::new(Rom::new(vec![0x5678; 2])))
+        .unwrap();
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(processor.peek(0x4001), 0x5678);
+    assert_eq!(processor.memory()[0x4001], 0);
+}
+
+#[test]
+fn set_memory_writes_to_devices() {
+    let mut processor = Processor::new_from_array([0; 16], [0; 65536], false)
+        .with_device(TIMER_BASE, Box::new(Timer::new()))
+        .unwrap();
+    processor.set_memory(TIMER_BASE + TIMER_RELOAD, 50);
+    assert_eq!(processor.peek(TIMER_BASE + TIMER_RELOAD), 50);
+    assert_eq!(processor.memory()[(TIMER_BASE + TIMER_RELOAD) as usize], 0);
+    processor.set_memory(0x8000, 0x1234);
+    assert_eq!(processor.peek(0x8000), 0x1234);
+}
+
+#[test]
+fn runs_from_rom() {
+    let program: Vec<u16> = assemble("setl r2, 7\nhalt").unwrap();
+    let mut processor = Processor::new_from_array([0; 16], [0; 65536], false)
+        .with_device(0, Box::new(Rom::new(program)))
+        .unwrap();
+    processor.set_history_limit(100);
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(processor.registers()[2], 7);
+    // nothing in a ROM changes, so stepping back is still allowed
+    assert!(!processor.has_stateful_devices());
+    assert!(processor.step_back_instruction());
+}
+
+#[test]
+fn overlapping_devices() {
+    let processor = Processor::new_from_array([0; 16], [0; 65536], false)
+        .with_device(0x1000, Box::new(Ram::new(16)))
+        .unwrap();
+    let overlap = processor.with_device(0x100F, Box::new(Ram::new(1)));
+    assert!(matches!(
+        overlap,
+        Err(SimError::DeviceMapping { start: 0x100F, .. })
+    ));
+    let past_end = Processor::new_from_array([0; 16], [0; 65536], false)
+        .with_device(0xFFF0, Box::new(Ram::new(17)));
+    assert!(matches!(past_end, Err(SimError::DeviceMapping { .. })));
+    Processor::new_from_array([0; 16], [0; 65536], false)
+        .with_device(0xFFF0, Box::new(Ram::new(16)))
+        .unwrap();
+}
@@ -0,0 +1,128 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+use simulator::{
+    assembler::assemble,
+    console::{Console, END_OF_INPUT},
+    disassembler::disassemble,
+    error::SimError,
+    processor::Processor,
+    types::{ExecutionMode, RunState, RunSummary, StopCause, StopReason},
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+/// Echoes every character of input back, then the number of characters read
+const ECHO: &str = "
+        setl r5, 1
+        setl r6, 0xFF
+        setu r6, 0xFF
+loop:   getc r2
+        setpcif done, r2, r6
+        putc r2
+        add r3, r3, r5
+        setpcif loop, r0, r0
+done:   putd r3
+        halt
+";
+
+/// A writer that can still be read after the console owning it has been dropped
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run a program with the given input, returning how it finished and what it wrote
+fn run(
+    source: &str,
+    input: &str,
+    execution_mode: ExecutionMode,
+) -> (Result<RunSummary, SimError>, String, Processor) {
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    let mut processor =
+        Processor::new_from_array([0; 16], memory, false).with_execution_mode(execution_mode);
+    let output = SharedBuffer::default();
+    processor.set_console(Console::new(
+        Box::new(std::io::Cursor::new(input.as_bytes().to_vec())),
+        Box::new(output.clone()),
+    ));
+    let summary = processor.run_until_halt(MAX_CYCLES);
+    let text = String::from_utf8(output.0.borrow().clone()).unwrap();
+    (summary, text, processor)
+}
+
+#[test]
+fn output() {
+    let source = "
+        setl r2, 0x41
+        setl r3, 0xE8
+        setu r3, 0x03
+        putc r2
+        putd r3
+        puth r3
+        halt
+    ";
+    for mode in [ExecutionMode::Cycle, ExecutionMode::Functional] {
+        let (summary, output, _) = run(source, "", mode);
+        assert_eq!(
+            summary.unwrap().state,
+            RunState::Stop(StopReason {
+                cause: StopCause::Halt(0),
+                pc: 6,
+                instruction: 0xF100
+            })
+        );
+        assert_eq!(output, "A100003E8");
+    }
+}
+
+#[test]
+fn echo() {
+    for mode in [ExecutionMode::Cycle, ExecutionMode::Functional] {
+        let (summary, output, processor) = run(ECHO, "hi\n", mode);
+        summary.unwrap();
+        assert_eq!(output, "hi\n3");
+        assert_eq!(processor.registers()[2], END_OF_INPUT);
+    }
+}
+
+#[test]
+fn read_decimal() {
+    let source = "
+        getd r2
+        getd r3
+        getd r4
+        halt
+    ";
+    let (summary, _, processor) = run(source, "1234\n  -2 \n", ExecutionMode::Cycle);
+    summary.unwrap();
+    assert_eq!(processor.registers()[2..5], [1234, 0xFFFE, END_OF_INPUT]);
+    let (summary, _, _) = run(source, "12a\n", ExecutionMode::Cycle);
+    assert!(matches!(summary, Err(SimError::Io(_))));
+}
+
+#[test]
+fn assembly() {
+    let words = assemble("putc r2\nputd r3\nputh pc\ngetc r15\ngetd r0").unwrap();
+    assert_eq!(words, [0xF220, 0xF330, 0xF410, 0xF5F0, 0xF600]);
+    let text: Vec<String> = words.iter().map(|x| disassemble(*x)).collect();
+    assert_eq!(
+        text,
+        ["putc r2", "putd r3", "puth r1", "getc r15", "getd r0"]
+    );
+    assert_eq!(disassemble(0xF221), ".word 0xF221");
+    assert_eq!(disassemble(0xFB00), ".word 0xFB00");
+}
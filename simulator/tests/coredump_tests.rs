@@ -0,0 +1,67 @@
+use rand::{thread_rng, Rng};
+use simulator::{coredump::CoreDump, processor::Processor};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+fn random_dump() -> CoreDump {
+    let mut rng = thread_rng();
+    let mut dump = CoreDump {
+        clock_cycle: rng.gen(),
+        registers: rng.gen(),
+        memory: vec![0; 65536],
+    };
+    for _ in 0..100 {
+        let address: usize = rng.gen_range(0..65536);
+        dump.memory[address] = rng.gen();
+    }
+    dump.memory[65535] = 0;
+    dump.memory[65534] = 0;
+    dump
+}
+
+#[test]
+fn full_and_compact_round_trip() {
+    for _ in 0..10 {
+        let dump = random_dump();
+        assert_eq!(CoreDump::parse(&dump.to_text()).unwrap(), dump);
+        assert_eq!(CoreDump::parse(&dump.to_compact_text()).unwrap(), dump);
+        assert!(dump.to_compact_text().len() < dump.to_text().len() / 100);
+    }
+}
+
+#[test]
+fn fixture_matches_run() {
+    let fixture = CoreDump::load("tests/fixtures/sum_countdown.dump").unwrap();
+    let mut processor = Processor::new_from_file(
+        String::from("../example_bytecode/sum_countdown.ayu"),
+        u64::MAX,
+        false,
+    )
+    .unwrap();
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(processor.to_core_dump(), fixture);
+}
+
+#[test]
+fn load_into_processor() {
+    // start from the dump of a halted program, rerunning the final halt
+    let mut fixture = CoreDump::load("tests/fixtures/sum_countdown.dump").unwrap();
+    fixture.registers[1] = 4;
+    fixture.registers[3] = 2;
+    let mut processor = Processor::new_from_coredump(&fixture, false).unwrap();
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(processor.registers()[4], 15 + 2 + 1);
+}
+
+#[test]
+fn reject_bad_dumps() {
+    let text = random_dump().to_compact_text();
+    assert!(CoreDump::parse("hello").is_err());
+    assert!(CoreDump::parse(&text.replace("R0x3: ", "R0x3 ")).is_err());
+    let truncated: Vec<&str> = text.lines().take(30).collect();
+    assert!(CoreDump::parse(&truncated.join("\n"))
+        .unwrap_err()
+        .to_string()
+        .contains("memory"));
+}
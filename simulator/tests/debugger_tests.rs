@@ -0,0 +1,255 @@
+use std::io::Cursor;
+
+use simulator::{
+    debugger::Debugger,
+    processor::Processor,
+    types::{Access, Breakpoint, Comparison, Opcode, RunState, State, Watchpoint, WatchpointHit},
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+fn sum_countdown() -> Processor {
+    Processor::new_from_file(
+        String::from("../example_bytecode/sum_countdown.ayu"),
+        u64::MAX,
+        false,
+    )
+    .unwrap()
+}
+
+fn debug(commands: &str) -> (Debugger, String) {
+    let mut debugger = Debugger::new(sum_countdown());
+    let mut output: Vec<u8> = Vec::new();
+    debugger
+        .run(Cursor::new(commands.to_string()), &mut output)
+        .unwrap();
+    (debugger, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn step_cycles_and_instructions() {
+    let (debugger, _output) = debug("step 2\n");
+    assert_eq!(debugger.processor().clock_cycle(), 2);
+    assert_eq!(*debugger.processor().state(), State::InstructionFetch);
+
+    let (debugger, _output) = debug("stepi 2\n");
+    assert_eq!(*debugger.processor().state(), State::PcRead);
+    assert_eq!(debugger.processor().registers()[1], 2);
+    assert_eq!(debugger.processor().registers()[3], 5);
+}
+
+#[test]
+fn breakpoints_stop_continue() {
+    let (debugger, output) = debug("break 0x5\ncontinue\n\nregisters\n");
+    assert!(output.contains("Reached breakpoint when pc == 0x0005"));
+    assert_eq!(debugger.processor().registers()[1], 5);
+    // the empty line repeats continue, so the loop has run twice
+    assert_eq!(debugger.processor().registers()[4], 9);
+
+    let (debugger, output) = debug("b 5\nd 5\nc\nc\n");
+    assert_eq!(output.matches("The program has finished").count(), 2);
+    assert_eq!(debugger.processor().registers()[4], 15);
+}
+
+#[test]
+fn inspect_and_modify() {
+    let (debugger, output) = debug("set r3 0x10\nset 0x100 42\nmemory 0x4 2\nm 0x100\nstate\nq\n");
+    assert_eq!(debugger.processor().registers()[3], 0x10);
+    assert_eq!(debugger.processor().memory()[0x100], 42);
+    assert!(output.contains("0x0004: 0x0443  add r4, r4, r3"));
+    assert!(output.contains("0x0005: 0x1332  sub r3, r3, r2"));
+    assert!(output.contains("0x0100: 0x002A"));
+    assert!(output.contains("register_write_source"));
+}
+
+#[test]
+fn parse_breakpoints() {
+    assert_eq!(
+        Breakpoint::from_string("0x10"),
+        Some(Breakpoint::Address(0x10))
+    );
+    assert_eq!(
+        Breakpoint::from_string("setpcif"),
+        Some(Breakpoint::Opcode(Opcode::SetPcIf))
+    );
+    assert_eq!(
+        Breakpoint::from_string("r3 <= 0x10"),
+        Some(Breakpoint::Register {
+            register: 3,
+            comparison: Comparison::LessOrEqual,
+            value: 0x10
+        })
+    );
+    assert_eq!(Breakpoint::from_string("r16==1"), None);
+    assert_eq!(Breakpoint::from_string("mul"), None);
+    // addresses and values have to fit in a word
+    assert_eq!(Breakpoint::from_string("0x10000"), None);
+    assert_eq!(Breakpoint::from_string("r3==65536"), None);
+}
+
+#[test]
+fn processor_breakpoints() {
+    let mut processor = sum_countdown();
+    processor.add_breakpoint(Breakpoint::Address(0x4));
+    processor.add_breakpoint(Breakpoint::Opcode(Opcode::Special));
+    let mut running = RunState::Continue;
+    let mut loop_heads = 0;
+    while running == RunState::Continue {
+        running = processor.step_instruction().unwrap();
+        if processor.hit_breakpoint() == Some(&Breakpoint::Address(0x4)) {
+            loop_heads += 1;
+            running = RunState::Continue;
+        }
+    }
+    assert_eq!(loop_heads, 5);
+    assert_eq!(
+        processor.hit_breakpoint(),
+        Some(&Breakpoint::Opcode(Opcode::Special))
+    );
+    assert_eq!(processor.registers()[1], 0x8);
+    assert_eq!(processor.registers()[4], 15);
+
+    let mut processor = sum_countdown();
+    let condition = Breakpoint::from_string("r4 == 12").unwrap();
+    processor.add_breakpoint(condition.clone());
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(processor.hit_breakpoint(), Some(&condition));
+    assert_eq!(processor.registers()[3], 3);
+    assert_eq!(*processor.state(), State::PcRead);
+    assert!(processor.remove_breakpoint(&condition));
+    assert!(processor.breakpoints().is_empty());
+}
+
+#[test]
+fn watchpoints() {
+    assert_eq!(
+        Watchpoint::from_string("0x10:rw"),
+        Some(Watchpoint::Memory {
+            address: 0x10,
+            access: Access::ReadWrite
+        })
+    );
+    assert_eq!(Watchpoint::from_string("r16"), None);
+
+    let mut register_state = [0; 16];
+    register_state[2] = 0x20;
+    register_state[3] = 0xBEEF;
+    let mut memory_state = [0; 65536];
+    memory_state[0] = 0x9310; // setl r3, 0x10
+    memory_state[1] = 0xC032; // sw r3, 0(r2)
+    memory_state[2] = 0xB042; // lw r4, 0(r2)
+    memory_state[3] = 0xF100; // halt
+    memory_state[0x20] = 0x1234;
+    let mut processor = Processor::new_from_array(register_state, memory_state, false);
+    processor.add_watchpoint(Watchpoint::Register(3));
+    processor.add_watchpoint(Watchpoint::from_string("0x20").unwrap());
+
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(
+        processor.watchpoint_hit(),
+        Some(&WatchpointHit {
+            watchpoint: Watchpoint::Register(3),
+            pc: 0,
+            instruction: 0x9310,
+            old_value: 0xBEEF,
+            new_value: 0x10,
+        })
+    );
+
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(
+        processor.watchpoint_hit(),
+        Some(&WatchpointHit {
+            watchpoint: Watchpoint::from_string("0x20:w").unwrap(),
+            pc: 1,
+            instruction: 0xC032,
+            old_value: 0x1234,
+            new_value: 0x10,
+        })
+    );
+
+    // reads are not watched unless asked for
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(processor.watchpoint_hit(), None);
+    assert_eq!(processor.registers()[4], 0x10);
+}
+
+#[test]
+fn step_back() {
+    let mut processor = sum_countdown();
+    processor.set_history_limit(1000);
+    let mut states = Vec::new();
+    let mut running = RunState::Continue;
+    while running == RunState::Continue {
+        states.push((
+            processor.clock_cycle(),
+            *processor.registers(),
+            format!("{:?}", processor.pipeline_registers()),
+        ));
+        running = processor.run().unwrap();
+    }
+    assert_eq!(processor.registers()[4], 15);
+
+    // step back through every cycle, checking the state matches what it was going forwards
+    while let Some((clock_cycle, registers, pipeline_registers)) = states.pop() {
+        assert!(processor.step_back());
+        assert_eq!(processor.clock_cycle(), clock_cycle);
+        assert_eq!(*processor.registers(), registers);
+        assert_eq!(
+            format!("{:?}", processor.pipeline_registers()),
+            pipeline_registers
+        );
+    }
+    assert!(!processor.step_back());
+
+    // replaying gives the same result
+    while processor.step_instruction().unwrap() == RunState::Continue {}
+    assert_eq!(processor.registers()[4], 15);
+    assert!(processor.rewind_to(30));
+    assert_eq!(processor.clock_cycle(), 30);
+    assert!(processor.step_back_instruction());
+    assert_eq!(*processor.state(), State::PcRead);
+
+    // memory writes are undone and the limit is kept to
+    let mut memory_state = [0; 65536];
+    memory_state[0] = 0xC020; // sw r2, 0(r0)
+    memory_state[1] = 0xF100;
+    let mut register_state = [0; 16];
+    register_state[2] = 0xABCD;
+    let mut processor = Processor::new_from_array(register_state, memory_state, false);
+    processor.set_history_limit(3);
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(processor.memory()[0], 0xABCD);
+    assert_eq!(processor.history_length(), 3);
+    while processor.step_back() {}
+    assert_eq!(processor.memory()[0], 0xABCD);
+    let mut processor = Processor::new_from_array(register_state, memory_state, false);
+    processor.set_history_limit(100);
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert!(processor.rewind_to(0));
+    assert_eq!(processor.memory()[0], 0xC020);
+}
+
+#[test]
+fn debugger_step_back() {
+    let (debugger, output) = debug("c\nback 2\nbacki\nrewind 10\n");
+    assert!(output.contains("The program has finished"));
+    assert_eq!(debugger.processor().clock_cycle(), 10);
+    let (debugger, _output) = debug("stepi 3\nbacki 2\n");
+    assert_eq!(debugger.processor().registers()[1], 1);
+}
+
+#[test]
+fn rewind_keeps_halt_state() {
+    // rewinding to a cycle after the halt leaves the program finished
+    let (debugger, output) = debug("c\nrewind 100000\nc\n");
+    assert!(output.contains("The program has finished\n"));
+    let halt_cycle: u64 = debugger.processor().clock_cycle();
+
+    // rewinding before the halt lets the program run to the end again
+    let (debugger, output) = debug(&format!("c\nrewind {}\nc\n", halt_cycle - 1));
+    assert!(!output.contains("The program has finished\n"));
+    assert_eq!(output.matches("The program has finished, ").count(), 2);
+    assert_eq!(debugger.processor().clock_cycle(), halt_cycle);
+}
@@ -0,0 +1,90 @@
+use simulator::{
+    coredump::CoreDump,
+    diff::{compare, format_differences, Difference, Expectations, Location},
+};
+
+fn fixture() -> CoreDump {
+    CoreDump::parse(include_str!("fixtures/sum_countdown.dump")).unwrap()
+}
+
+#[test]
+fn identical_dumps_match() {
+    let dump = fixture();
+    assert!(compare(&Expectations::from_dump(&dump), &dump).is_empty());
+}
+
+#[test]
+fn report_differences() {
+    let expected = fixture();
+    let mut actual = fixture();
+    actual.registers[4] = 0x10;
+    actual.memory[0] ^= 0x0010;
+    actual.memory[0x100] = 0x1234;
+    let differences = compare(&Expectations::from_dump(&expected), &actual);
+    assert_eq!(
+        differences,
+        vec![
+            Difference {
+                location: Location::Register(4),
+                expected: 0x000F,
+                actual: 0x0010,
+            },
+            Difference {
+                location: Location::Memory(0),
+                expected: expected.memory[0],
+                actual: actual.memory[0],
+            },
+            Difference {
+                location: Location::Memory(0x100),
+                expected: 0,
+                actual: 0x1234,
+            },
+        ]
+    );
+    let table = format_differences(&differences, 9);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("R4"));
+    // code is disassembled, data past the halt is not
+    assert!(lines[2].contains(" -> "));
+    assert_eq!(lines[3].trim_end(), "M0x0100   0x0000    0x1234");
+}
+
+#[test]
+fn partial_expectations() {
+    let expectations = Expectations::parse(
+        "# the result of the countdown\n\
+         R0x4: 0x000F\n\
+         M0x0020-0x002F: 0x0000\n",
+    )
+    .unwrap();
+    assert_eq!(expectations.registers.len(), 1);
+    assert_eq!(expectations.memory.len(), 16);
+    let mut dump = fixture();
+    assert!(compare(&expectations, &dump).is_empty());
+    dump.registers[5] = 1;
+    assert!(compare(&expectations, &dump).is_empty());
+    dump.memory[0x25] = 1;
+    assert_eq!(compare(&expectations, &dump).len(), 1);
+    assert!(Expectations::parse("R0x10: 0x0000").is_err());
+    assert!(Expectations::parse("M0x0002-0x0001: 0x0000").is_err());
+    assert!(Expectations::parse("\u{e9}0x0: 0x0000").is_err());
+}
+
+#[test]
+fn code_length_sets_what_is_disassembled() {
+    let expected = fixture();
+    let mut actual = fixture();
+    // a word that looks like a halt at the start of the code doesn't end it
+    actual.memory[0] = 0xF100;
+    actual.memory[5] = 0x1234;
+    let differences = compare(&Expectations::from_dump(&expected), &actual);
+    let table = format_differences(&differences, 9);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].ends_with("-> halt"), "{}", lines[1]);
+    assert!(lines[2].starts_with("M0x0005") && lines[2].contains(" -> "));
+    // nothing is disassembled without a program
+    let table = format_differences(&differences, 0);
+    assert!(!table.contains(" -> "));
+}
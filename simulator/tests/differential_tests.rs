@@ -0,0 +1,150 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use simulator::{
+    disassembler::{disassemble, disassemble_memory},
+    processor::Processor,
+    reference::{Reference, DIVERGENT_OPCODES},
+    types::{ExecutionMode, Opcode, RunState, StopCause},
+};
+
+const PROGRAMS: u64 = 200;
+const PROGRAM_LENGTH: usize = 32;
+/// Instructions run per program, random branches mean most programs never halt
+const MAX_INSTRUCTIONS: usize = 100;
+
+/// Random instructions using every opcode except special and the divergent ones, with the
+/// occasional halt
+fn random_program(rng: &mut StdRng) -> Vec<u16> {
+    let opcodes: Vec<u16> = (0..=0xD)
+        .filter(|x| !DIVERGENT_OPCODES.contains(&Opcode::from_u8(*x as u8)))
+        .collect();
+    (0..PROGRAM_LENGTH)
+        .map(|_| match rng.gen_range(0..20) {
+            0 => 0xF100 | rng.gen_range(0..0x100),
+            _ => opcodes[rng.gen_range(0..opcodes.len())] << 12 | rng.gen_range(0..0x1000),
+        })
+        .collect()
+}
+
+/// Describe how the processor differs from the reference, None if they agree
+fn divergence(processor: &Processor, reference: &Reference) -> Option<String> {
+    let mut differences: Vec<String> = (0..16)
+        .filter(|x| processor.registers()[*x] != reference.registers[*x])
+        .map(|x| {
+            format!(
+                "R{}: expected {:#06X}, processor has {:#06X}",
+                x,
+                reference.registers[x],
+                processor.registers()[x]
+            )
+        })
+        .collect();
+    // the whole of memory is only searched once it is known to differ
+    if processor.memory()[..] != reference.memory[..] {
+        differences.extend(
+            (0..65536)
+                .filter(|x| processor.memory()[*x] != reference.memory[*x])
+                .map(|x| {
+                    format!(
+                        "M{:#06X}: expected {:#06X}, processor has {:#06X}",
+                        x,
+                        reference.memory[x],
+                        processor.memory()[x]
+                    )
+                }),
+        );
+    }
+    match differences.is_empty() {
+        true => None,
+        false => Some(differences.join("\n")),
+    }
+}
+
+/// Run random programs on the processor and the reference, checking they agree after every
+/// instruction retires
+fn differential(execution_mode: ExecutionMode) {
+    for seed in 0..PROGRAMS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let program: Vec<u16> = random_program(&mut rng);
+        let mut registers: [u16; 16] = rng.gen();
+        // programs start at the beginning of memory
+        registers[1] = 0;
+        let mut memory = [0; 65536];
+        memory[..program.len()].copy_from_slice(&program);
+        let mut processor =
+            Processor::new_from_array(registers, memory, false).with_execution_mode(execution_mode);
+        let mut reference = Reference::new(registers, &memory);
+        for step in 0..MAX_INSTRUCTIONS {
+            let pc: u16 = reference.registers[1];
+            let instruction: u16 = reference.memory[pc as usize];
+            // programs can write divergent instructions into memory as they run
+            if DIVERGENT_OPCODES.contains(&Opcode::from_u8((instruction >> 12) as u8)) {
+                break;
+            }
+            let report = |problem: String| {
+                panic!(
+                    "seed {} diverged at instruction {}, {:#06X}: {:#06X}  {}\n{}\nProgram:\n{}",
+                    seed,
+                    step,
+                    pc,
+                    instruction,
+                    disassemble(instruction),
+                    problem,
+                    disassemble_memory(&program).join("\n")
+                )
+            };
+            let halted: bool = match (reference.step(), processor.step_instruction()) {
+                (Ok(None), Ok(RunState::Continue)) => false,
+                (Ok(Some(exit_code)), Ok(RunState::Stop(reason)))
+                    if reason.cause == StopCause::Halt(exit_code) =>
+                {
+                    true
+                }
+                // both rejected the instruction, there is no state left to compare
+                (Err(expected), Err(actual))
+                    if std::mem::discriminant(&expected) == std::mem::discriminant(&actual) =>
+                {
+                    break
+                }
+                (expected, actual) => report(format!(
+                    "expected {:?}, processor returned {:?}",
+                    expected, actual
+                )),
+            };
+            if let Some(difference) = divergence(&processor, &reference) {
+                report(difference);
+            }
+            if halted {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn cycle_matches_reference() {
+    differential(ExecutionMode::Cycle);
+}
+
+#[test]
+fn functional_matches_reference() {
+    differential(ExecutionMode::Functional);
+}
+
+#[test]
+fn reference_shift_right_arithmetic() {
+    for (a, b, expected) in [
+        (0x0004, 1, 0x0002),
+        (0x8000, 1, 0xC000),
+        (0xF0F0, 4, 0xFF0F),
+        (0x7FFF, 20, 0x0000),
+        (0x8001, 20, 0xFFFF),
+    ] {
+        let mut registers: [u16; 16] = [0; 16];
+        registers[2] = a;
+        registers[3] = b;
+        // sra r4, r2, r3
+        let mut reference = Reference::new(registers, &[0x8423]);
+        reference.step().unwrap();
+        assert_eq!(reference.registers[4], expected, "{:#06X} >> {}", a, b);
+    }
+}
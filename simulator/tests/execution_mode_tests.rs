@@ -0,0 +1,123 @@
+use simulator::{
+    assembler::assemble,
+    error::SimError,
+    processor::Processor,
+    types::{Breakpoint, ExecutionMode, RunState, Watchpoint},
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+/// Uses every opcode in a loop that runs 3 times
+const EVERY_OPCODE: &str = "
+        setl r2, 0x34
+        setu r2, 0x12
+        setl r3, 4
+        setl r12, data
+        setl r14, 3
+loop:   sra r4, r2, r3
+        sub r7, r3, r2
+        slt r10, r3, r2
+        seq r11, r3, r3
+        sw r4, 1(r12)
+        lw r13, 1(r12)
+        sub r14, r14, r11
+        setpcif done, r14, r0
+        setpcif loop, r0, r0
+done:   and r8, r2, r7
+        or r9, r13, r3
+        sll r5, r2, r3
+        srl r6, r2, r3
+        halt 0x2A
+data:   .word 0, 0
+";
+
+fn processor(source: &str, execution_mode: ExecutionMode) -> Processor {
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    Processor::new_from_array([0; 16], memory, false).with_execution_mode(execution_mode)
+}
+
+/// Step both modes an instruction at a time, checking they agree at every boundary
+fn compare_modes(source: &str) {
+    let mut cycle = processor(source, ExecutionMode::Cycle);
+    let mut functional = processor(source, ExecutionMode::Functional);
+    loop {
+        let expected = cycle.step_instruction().unwrap();
+        let running = functional.step_instruction().unwrap();
+        assert_eq!(running, expected);
+        assert_eq!(functional.clock_cycle(), cycle.clock_cycle());
+        assert_eq!(functional.registers(), cycle.registers());
+        assert_eq!(functional.state(), cycle.state());
+        assert_eq!(functional.pipeline_registers(), cycle.pipeline_registers());
+        assert_eq!(functional.memory(), cycle.memory());
+        if running != RunState::Continue {
+            break;
+        }
+    }
+}
+
+#[test]
+fn functional_matches_cycle() {
+    compare_modes(EVERY_OPCODE);
+    compare_modes(&std::fs::read_to_string("../example_bytecode/sum_countdown.ayu").unwrap());
+}
+
+#[test]
+fn functional_runs_one_instruction_per_step() {
+    let mut processor = processor(EVERY_OPCODE, ExecutionMode::Functional);
+    let summary = processor.run_until_halt(MAX_CYCLES).unwrap();
+    let mut expected = self::processor(EVERY_OPCODE, ExecutionMode::Cycle);
+    let expected_summary = expected.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(summary.state, expected_summary.state);
+    assert_eq!(summary.instructions, expected_summary.instructions);
+    assert_eq!(summary.cycles, expected_summary.cycles);
+    assert_eq!(processor.clock_cycle(), expected.clock_cycle());
+    assert_eq!(processor.registers()[9], expected.registers()[9]);
+}
+
+#[test]
+fn functional_run_limits_count_clock_cycles() {
+    // count r2 up forever
+    let source = "setl r3, 1\nloop: add r2, r2, r3\nsetpcif loop, r0, r0";
+    let mut processor = processor(source, ExecutionMode::Functional);
+    let start = processor.clock_cycle();
+    let summary = processor.run_cycles(10).unwrap();
+    assert_eq!(summary.cycles, processor.clock_cycle() - start);
+    // whole instructions run, so the run can end part way into the next one
+    assert!((10..15).contains(&summary.cycles));
+
+    let start = processor.clock_cycle();
+    assert!(matches!(
+        processor.run_until_halt(100),
+        Err(SimError::Watchdog { cycles: 100 })
+    ));
+    assert!((100..105).contains(&(processor.clock_cycle() - start)));
+}
+
+#[test]
+fn functional_debugging() {
+    let mut processor = processor(EVERY_OPCODE, ExecutionMode::Functional);
+    processor.set_history_limit(100);
+    processor.add_breakpoint(Breakpoint::Address(0x9));
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(processor.registers()[1], 0x9);
+    let clock_cycle = processor.clock_cycle();
+    let registers = *processor.registers();
+
+    processor.clear_breakpoints();
+    processor.add_watchpoint(Watchpoint::from_string("r13").unwrap());
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    let hit = processor.watchpoint_hit().unwrap();
+    assert_eq!(hit.pc, 0xA);
+    assert_eq!(processor.registers()[1], 0xB);
+
+    // back over the lw that hit the watchpoint and the sw after the breakpoint
+    assert!(processor.step_back_instruction());
+    assert_eq!(processor.registers()[1], 0xA);
+    assert!(processor.step_back_instruction());
+    assert_eq!(processor.clock_cycle(), clock_cycle);
+    assert_eq!(processor.registers(), &registers);
+}
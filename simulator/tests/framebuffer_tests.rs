@@ -0,0 +1,106 @@
+use simulator::{
+    assembler::assemble,
+    devices::{Framebuffer, FRAMEBUFFER_BASE, FRAMEBUFFER_HEIGHT, FRAMEBUFFER_WIDTH},
+    frame::{numbered_path, rgb565_to_rgb, Frame},
+    processor::Processor,
+    types::ExecutionMode,
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+fn load(path: &str, execution_mode: ExecutionMode) -> Processor {
+    let source = std::fs::read_to_string(path).unwrap();
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(&source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    Processor::new_from_array([0; 16], memory, false)
+        .with_execution_mode(execution_mode)
+        .with_device(FRAMEBUFFER_BASE, Box::new(Framebuffer::default()))
+        .unwrap()
+}
+
+#[test]
+fn diagonal_program() {
+    for mode in [ExecutionMode::Cycle, ExecutionMode::Functional] {
+        let mut processor = load("../example_bytecode/framebuffer_diagonal.ayu", mode);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let frame = processor.frame().unwrap();
+        assert_eq!(
+            (frame.width, frame.height),
+            (FRAMEBUFFER_WIDTH, FRAMEBUFFER_HEIGHT)
+        );
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                let expected = if x == y { 0x001F } else { 0 };
+                assert_eq!(frame.pixel(x, y), expected, "({}, {}) {:?}", x, y, mode);
+            }
+        }
+        assert_eq!(processor.peek(FRAMEBUFFER_BASE + 65), 0x001F);
+    }
+}
+
+#[test]
+fn frames_saved_to_files() {
+    let directory = std::env::temp_dir().join("ayu_framebuffer_test");
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("diagonal.ppm");
+    let path = path.to_str().unwrap();
+    let numbered = numbered_path(path, 1);
+    assert!(numbered.ends_with("diagonal-0001.ppm"));
+    let mut processor = load(
+        "../example_bytecode/framebuffer_diagonal.ayu",
+        ExecutionMode::Functional,
+    );
+    processor.set_frame_path(Some(path.to_string()));
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    let expected = processor.frame().unwrap().to_ppm();
+    assert_eq!(std::fs::read(path).unwrap(), expected);
+    assert_eq!(std::fs::read(&numbered).unwrap(), expected);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn ppm_encoding() {
+    let frame = Frame {
+        width: 2,
+        height: 1,
+        pixels: vec![0xF800, 0x07E0],
+    };
+    let mut expected = b"P6\n2 1\n255\n".to_vec();
+    expected.extend([255, 0, 0, 0, 255, 0]);
+    assert_eq!(frame.to_ppm(), expected);
+    assert_eq!(rgb565_to_rgb(0xFFFF), [255, 255, 255]);
+    assert_eq!(rgb565_to_rgb(0x8410), [132, 130, 132]);
+}
+
+#[test]
+fn png_encoding() {
+    let frame = Frame {
+        width: 1,
+        height: 1,
+        pixels: vec![0x001F],
+    };
+    let png = frame.to_png();
+    assert_eq!(png[..8], [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+    // IHDR of a 1x1 8 bit RGB image, with it's CRC
+    assert_eq!(
+        png[8..33],
+        [
+            0, 0, 0, 13, b'I', b'H', b'D', b'R', 0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0, 0x90, 0x77,
+            0x53, 0xDE
+        ]
+    );
+    // a single stored block holding the filter byte and pixel
+    let idat = &png[33..png.len() - 12];
+    assert_eq!(idat[4..8], *b"IDAT");
+    assert_eq!(
+        idat[8..idat.len() - 4],
+        [0x78, 0x01, 1, 4, 0, 0xFB, 0xFF, 0, 0, 0, 255, 0x01, 0x03, 0x01, 0x00]
+    );
+    assert_eq!(
+        png[png.len() - 12..],
+        [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]
+    );
+}
@@ -0,0 +1,163 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use std::time::Duration;
+
+use simulator::{assembler::assemble, gdbstub::GdbStub, processor::Processor};
+
+/// Send a packet and return the reply, checking both are acknowledged
+fn request(stream: &mut TcpStream, data: &str) -> String {
+    send(stream, data);
+    reply(stream)
+}
+
+/// Send a packet, checking it is acknowledged
+fn send(stream: &mut TcpStream, data: &str) {
+    let checksum: u8 = data.bytes().fold(0, |sum, x| sum.wrapping_add(x));
+    write!(stream, "${}#{:02x}", data, checksum).unwrap();
+    let mut byte = [0; 1];
+    stream.read_exact(&mut byte).unwrap();
+    assert_eq!(byte[0], b'+');
+}
+
+/// Wait for a reply packet and acknowledge it
+fn reply(stream: &mut TcpStream) -> String {
+    let mut byte = [0; 1];
+    stream.read_exact(&mut byte).unwrap();
+    assert_eq!(byte[0], b'$');
+    let mut reply = Vec::new();
+    loop {
+        stream.read_exact(&mut byte).unwrap();
+        if byte[0] == b'#' {
+            break;
+        }
+        reply.push(byte[0]);
+    }
+    let mut checksum = [0; 2];
+    stream.read_exact(&mut checksum).unwrap();
+    let expected = reply.iter().fold(0u8, |sum, x| sum.wrapping_add(*x));
+    assert_eq!(
+        u8::from_str_radix(std::str::from_utf8(&checksum).unwrap(), 16).unwrap(),
+        expected
+    );
+    stream.write_all(b"+").unwrap();
+    String::from_utf8(reply).unwrap()
+}
+
+#[test]
+fn gdb_session() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let processor = Processor::new_from_file(
+            String::from("../example_bytecode/sum_countdown.ayu"),
+            u64::MAX,
+            false,
+        )
+        .unwrap();
+        let mut stub = GdbStub::new(processor);
+        let (stream, _) = listener.accept().unwrap();
+        stub.serve(stream).unwrap();
+        stub.processor().registers()[4]
+    });
+    let mut stream = TcpStream::connect(address).unwrap();
+
+    assert!(request(&mut stream, "qSupported:multiprocess+").starts_with("PacketSize"));
+    assert_eq!(request(&mut stream, "?"), "S05");
+    assert_eq!(request(&mut stream, "vMustReplyEmpty"), "");
+    // unknown commands get an empty reply, even when they aren't ASCII
+    assert_eq!(request(&mut stream, "\u{e9}1"), "");
+
+    // memory is word addressed, words are little endian
+    assert_eq!(request(&mut stream, "m0,4"), "01920593");
+    assert_eq!(request(&mut stream, "m4,1"), "43");
+
+    assert_eq!(request(&mut stream, "s"), "S05");
+    assert_eq!(request(&mut stream, "p1"), "0100");
+    assert_eq!(request(&mut stream, "p2"), "0100");
+
+    // stop at the loop head each time around
+    assert_eq!(request(&mut stream, "Z0,4,2"), "OK");
+    assert_eq!(request(&mut stream, "c"), "S05");
+    assert_eq!(request(&mut stream, "p1"), "0400");
+    assert_eq!(request(&mut stream, "c"), "S05");
+    let registers = request(&mut stream, "g");
+    assert_eq!(registers.len(), 64);
+    assert_eq!(&registers[16..20], "0500");
+
+    // start the count from 2 instead of 4 so the total is 5 + 2 + 1
+    assert_eq!(request(&mut stream, "P3=0200"), "OK");
+    assert_eq!(request(&mut stream, "M100,2:efbe"), "OK");
+    assert_eq!(request(&mut stream, "m100,2"), "efbe");
+    // addresses past the end of memory and reads bigger than a packet are refused,
+    // accesses running off the end wrap around
+    assert_eq!(request(&mut stream, "m10000,2"), "E01");
+    assert_eq!(request(&mut stream, "mffffffffffffffff,2"), "E01");
+    assert_eq!(request(&mut stream, "m0,ffffffffff"), "E01");
+    assert_eq!(request(&mut stream, "Mffff,4:34127856"), "OK");
+    assert_eq!(request(&mut stream, "mffff,4"), "34127856");
+    assert_eq!(request(&mut stream, "Mffffffffffffffff,2:0000"), "E01");
+
+    assert_eq!(request(&mut stream, "z0,4,2"), "OK");
+    assert_eq!(request(&mut stream, "c"), "W00");
+    assert_eq!(request(&mut stream, "D"), "OK");
+    assert_eq!(server.join().unwrap(), 8);
+}
+
+#[test]
+fn clock_cycle_stop_keeps_the_session() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let processor = Processor::new_from_file(
+            String::from("../example_bytecode/sum_countdown.ayu"),
+            20,
+            false,
+        )
+        .unwrap();
+        let mut stub = GdbStub::new(processor);
+        let (stream, _) = listener.accept().unwrap();
+        stub.serve(stream).unwrap();
+    });
+    let mut stream = TcpStream::connect(address).unwrap();
+
+    // stopped by the clock cycle breakpoint, not exited
+    assert_eq!(request(&mut stream, "c"), "S05");
+    assert_eq!(request(&mut stream, "?"), "S05");
+    assert_eq!(request(&mut stream, "g").len(), 64);
+    assert_eq!(request(&mut stream, "m0,2"), "0192");
+    assert_eq!(request(&mut stream, "s"), "S05");
+    assert_eq!(request(&mut stream, "k"), "OK");
+    server.join().unwrap();
+}
+
+#[test]
+fn interrupt_a_running_program() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let mut memory = [0; 65536];
+        for (i, word) in assemble("loop: setpcif loop, r0, r0")
+            .unwrap()
+            .into_iter()
+            .enumerate()
+        {
+            memory[i] = word;
+        }
+        let mut stub = GdbStub::new(Processor::new_from_array([0; 16], memory, false));
+        let (stream, _) = listener.accept().unwrap();
+        stub.serve(stream).unwrap();
+    });
+    let mut stream = TcpStream::connect(address).unwrap();
+
+    // the program never halts, so only Ctrl-C stops it
+    send(&mut stream, "c");
+    thread::sleep(Duration::from_millis(50));
+    stream.write_all(&[0x03]).unwrap();
+    assert_eq!(reply(&mut stream), "S02");
+    assert_eq!(request(&mut stream, "?"), "S02");
+    assert_eq!(request(&mut stream, "s"), "S05");
+    assert_eq!(request(&mut stream, "k"), "OK");
+    server.join().unwrap();
+}
@@ -1,5 +1,13 @@
 use rand::{thread_rng, Rng};
-use simulator::{self, processor::Processor, types::RunState};
+use simulator::{
+    self,
+    error::SimError,
+    processor::Processor,
+    types::{RunState, StopCause, StopReason},
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
 
 #[test]
 fn add() {
@@ -14,11 +22,8 @@ fn add() {
         memory_state[0] = 0x0223;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         assert_eq!(_dump_registers[2], a.wrapping_add(b));
     }
 }
@@ -36,11 +41,8 @@ fn subtract() {
         memory_state[0] = 0x1223;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         if a < b {
             assert_eq!(_dump_registers[2], (a as i16).wrapping_sub(b as i16) as u16);
         } else {
@@ -62,11 +64,8 @@ fn and() {
         memory_state[0] = 0x2223;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         assert_eq!(_dump_registers[2], a & b);
     }
 }
@@ -84,11 +83,8 @@ fn or() {
         memory_state[0] = 0x3223;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         assert_eq!(_dump_registers[2], a | b);
     }
 }
@@ -106,11 +102,8 @@ fn set_if_less() {
         memory_state[0] = 0x4223;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         if (a < b) != (_dump_registers[2] == 1) {
             println!("a: {:016b}, b: {:016b}", a, b);
         }
@@ -135,11 +128,8 @@ fn set_if_equal() {
         memory_state[0] = 0x5223;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         if (a < b) != (_dump_registers[2] == 1) {
             println!("a: {}, b: {}", a, b);
         }
@@ -160,11 +150,8 @@ fn shift_left() {
         memory_state[0] = 0x6223;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         assert_eq!(_dump_registers[2], a.checked_shl(b as u32).unwrap_or(0));
     }
 }
@@ -182,11 +169,8 @@ fn shift_right_logical() {
         memory_state[0] = 0x7223;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         assert_eq!(_dump_registers[2], a.checked_shr(b as u32).unwrap_or(0));
     }
 }
@@ -204,11 +188,8 @@ fn shift_right_arithmetic() {
         memory_state[0] = 0x8223;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         let shift_result: u16 = a.checked_shr(b as u32).unwrap_or(0);
         let result: u16 =
             shift_result | ((0b1111111111111111 << shift_result.leading_zeros()) & 0xFFFF) as u16;
@@ -226,11 +207,8 @@ fn set_lower() {
         memory_state[0] = 0x9200 | a;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         assert_eq!(_dump_registers[2], a);
     }
 }
@@ -247,11 +225,8 @@ fn set_upper() {
         memory_state[0] = 0xA200 + b;
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         assert_eq!(_dump_registers[2], b << 8 | a);
     }
 }
@@ -270,11 +245,8 @@ fn load_word() {
         memory_state[1] = 0xF100;
         memory_state[(address + offset) as usize] = word;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         if _dump_registers[2] != word {
             println!("word: {}, address: {}, offset: {}", word, address, offset);
             println!("expected: {}, actual: {}", word, _dump_registers[2]);
@@ -297,11 +269,8 @@ fn save_word() {
         memory_state[0] = 0xC032 | (offset << 12);
         memory_state[1] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         assert_eq!(_dump_memory[(address + offset) as usize], word);
     }
 }
@@ -324,11 +293,86 @@ fn set_pc_if() {
         memory_state[1] = 0x9301;
         memory_state[2] = 0xF100;
         let mut processor = Processor::new_from_array(register_state, memory_state, false);
-        let mut running = RunState::Continue;
-        while running == RunState::Continue {
-            running = processor.run();
-        }
-        let (_dump_registers, _dump_memory) = processor.coredump(false);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        let (_dump_registers, _dump_memory) = processor.coredump(false).unwrap();
         assert_eq!(_dump_registers[3], (a == b) as u16);
     }
 }
+
+#[test]
+fn invalid_opcode() {
+    let mut memory_state = [0; 65536];
+    memory_state[0] = 0x9201;
+    memory_state[1] = 0xE123;
+    let mut processor = Processor::new_from_array([0; 16], memory_state, false);
+    let error = loop {
+        match processor.run() {
+            Ok(RunState::Continue) => (),
+            Ok(RunState::Stop(_)) => panic!("invalid opcode was executed"),
+            Err(error) => break error,
+        }
+    };
+    assert!(matches!(
+        error,
+        SimError::InvalidOpcode {
+            pc: 1,
+            instruction: 0xE123
+        }
+    ));
+    assert_eq!(processor.registers()[2], 1);
+}
+
+#[test]
+fn missing_file() {
+    let error = Processor::new_from_file(String::from("missing.ayu"), u64::MAX, false);
+    assert!(matches!(error, Err(SimError::Io(_))));
+}
+
+#[test]
+fn halt_exit_code() {
+    let mut memory_state = [0; 65536];
+    memory_state[0] = 0x9201;
+    memory_state[1] = 0xF12A;
+    let mut processor = Processor::new_from_array([0; 16], memory_state, false);
+    let summary = processor.run_until_halt(MAX_CYCLES).unwrap();
+    let reason = StopReason {
+        cause: StopCause::Halt(0x2A),
+        pc: 1,
+        instruction: 0xF12A,
+    };
+    assert_eq!(reason.exit_code(), 0x2A);
+    assert_eq!(summary.state, RunState::Stop(reason));
+    assert_eq!(summary.instructions, 1);
+    // the clock is left on the cycle the halt finished in
+    assert_eq!(summary.cycles, processor.clock_cycle());
+}
+
+#[test]
+fn run_limits() {
+    let mut memory_state = [0; 65536];
+    // count r2 up forever
+    memory_state[0] = 0x9301;
+    memory_state[1] = 0x0223;
+    memory_state[2] = 0xD100;
+    let mut processor = Processor::new_from_array([0; 16], memory_state, false);
+    let summary = processor.run_instructions(4).unwrap();
+    assert_eq!(summary.state, RunState::Continue);
+    assert_eq!(summary.instructions, 4);
+    assert_eq!(processor.registers()[2], 2);
+
+    let start = processor.clock_cycle();
+    let summary = processor.run_cycles(7).unwrap();
+    assert_eq!(summary.cycles, 7);
+    assert_eq!(processor.clock_cycle(), start + 7);
+
+    let summary = processor
+        .run_until(MAX_CYCLES, |x| x.registers()[2] == 10)
+        .unwrap();
+    assert_eq!(summary.state, RunState::Continue);
+    assert_eq!(processor.registers()[2], 10);
+
+    assert!(matches!(
+        processor.run_until_halt(MAX_CYCLES),
+        Err(SimError::Watchdog { cycles: MAX_CYCLES })
+    ));
+}
@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+use simulator::{
+    assembler::assemble,
+    devices::{Timer, Uart, TIMER_BASE, UART_BASE},
+    disassembler::disassemble,
+    processor::{Processor, INTERRUPT_VECTOR},
+    types::{ExecutionMode, State},
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+/// Echo input back through the UART, from a handler run whenever a byte is received
+const UART_ECHO: &str = "
+        setl r2, 0
+        setu r2, 0xFF           # r2 = 0xFF00, the UART
+        setl r3, 0xFF
+        setu r3, 0xFF           # r3 = 0xFFFF, the interrupt vector
+        setl r4, handler
+        sw r4, 0(r3)
+        setl r4, 1
+        sw r4, 2(r2)            # interrupt when a byte is received
+        ei
+wait:   setpcif wait, r0, r0
+done:   halt
+handler:
+        lw r5, 1(r2)
+        setl r4, 4
+        and r6, r5, r4
+        setpcif done, r6, r4    # the input has ended
+        lw r7, 0(r2)
+        sw r7, 0(r2)
+        rfi
+";
+
+/// Set up an interrupting timer, but disable interrupts again before it expires
+const DISABLED: &str = "
+        setl r2, 0x10
+        setu r2, 0xFF           # r2 = 0xFF10, the timer
+        setl r3, 0xFF
+        setu r3, 0xFF           # r3 = 0xFFFF, the interrupt vector
+        setl r4, handler
+        sw r4, 0(r3)
+        setl r4, 20
+        sw r4, 1(r2)
+        sw r0, 0(r2)            # restart the count
+        setl r4, 1
+        sw r4, 3(r2)
+        ei
+        di
+wait:   lw r5, 2(r2)
+        setpcif wait, r5, r0    # the timer hasn't expired
+        halt
+handler:
+        setl r6, 1
+        rfi
+";
+
+/// A writer that can still be read after the UART owning it has been dropped
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn load(source: &str, execution_mode: ExecutionMode) -> Processor {
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    Processor::new_from_array([0; 16], memory, false)
+        .with_execution_mode(execution_mode)
+        .with_device(TIMER_BASE, Box::new(Timer::new()))
+        .unwrap()
+}
+
+#[test]
+fn timer_interrupts() {
+    let source = std::fs::read_to_string("../example_bytecode/timer_interrupt.ayu").unwrap();
+    let mut cycle = load(&source, ExecutionMode::Cycle);
+    let mut functional = load(&source, ExecutionMode::Functional);
+    cycle.run_until_halt(MAX_CYCLES).unwrap();
+    functional.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(cycle.registers()[6], 5);
+    assert_eq!(cycle.registers(), functional.registers());
+    assert_eq!(cycle.clock_cycle(), functional.clock_cycle());
+    assert_eq!(cycle.stats().cycles, functional.stats().cycles);
+    assert_eq!(cycle.epc(), functional.epc());
+    assert!(cycle.interrupts_enabled());
+}
+
+#[test]
+fn interrupt_states() {
+    let source = std::fs::read_to_string("../example_bytecode/timer_interrupt.ayu").unwrap();
+    let mut processor = load(&source, ExecutionMode::Cycle);
+    while *processor.state() != State::InterruptSave {
+        processor.run().unwrap();
+        assert!(processor.clock_cycle() < MAX_CYCLES);
+    }
+    let pc = processor.registers()[1];
+    assert!(processor.control_signals().enter_interrupt);
+    assert_eq!(processor.epc(), pc);
+    assert!(!processor.interrupts_enabled());
+    let handler = processor.peek(INTERRUPT_VECTOR);
+    processor.run().unwrap();
+    assert_eq!(*processor.state(), State::InterruptVector);
+    assert_eq!(processor.registers()[1], handler);
+    processor.run().unwrap();
+    assert_eq!(*processor.state(), State::PcRead);
+    // the handler runs to rfi, returning to where the interrupt was taken
+    while disassemble(processor.instruction_register()) != "rfi"
+        || *processor.state() != State::PcRead
+    {
+        processor.run().unwrap();
+    }
+    assert_eq!(processor.registers()[1], pc);
+    assert!(processor.interrupts_enabled());
+}
+
+#[test]
+fn disabled_interrupts_are_not_taken() {
+    for mode in [ExecutionMode::Cycle, ExecutionMode::Functional] {
+        let mut processor = load(DISABLED, mode);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        assert_eq!(processor.registers()[6], 0, "{:?}", mode);
+        assert!(!processor.interrupts_enabled());
+    }
+}
+
+#[test]
+fn uart_interrupts() {
+    for mode in [ExecutionMode::Cycle, ExecutionMode::Functional] {
+        let output = SharedBuffer::default();
+        let uart = Uart::scripted(b"Hello\n", Box::new(output.clone()));
+        let mut processor = load(UART_ECHO, mode)
+            .with_device(UART_BASE, Box::new(uart))
+            .unwrap();
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        assert_eq!(*output.0.borrow(), b"Hello\n", "{:?}", mode);
+    }
+}
+
+#[test]
+fn interrupt_instructions() {
+    assert_eq!(
+        assemble("rfi\nei\ndi").unwrap(),
+        vec![0xF700, 0xF800, 0xF900]
+    );
+    assert_eq!(disassemble(0xF800), "ei");
+    assert!(assemble("ei r2").is_err());
+}
@@ -0,0 +1,75 @@
+use simulator::{
+    assembler::{assemble_program, Program},
+    processor::Processor,
+    profiler::Profile,
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+/// Counts down from 3, spending most of it's time in the loop
+const COUNTDOWN: &str = "
+        setl r2, 3
+        setl r3, 1
+loop:   sub r2, r2, r3
+        setpcif done, r2, r0
+        setpcif loop, r0, r0
+done:
+end:    halt
+";
+
+fn run(source: &str) -> (Processor, Program) {
+    let program = assemble_program(source).unwrap();
+    let mut memory = [0; 65536];
+    memory[..program.words.len()].copy_from_slice(&program.words);
+    let mut processor = Processor::new_from_array([0; 16], memory, false);
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    (processor, program)
+}
+
+#[test]
+fn labels() {
+    let program = assemble_program(COUNTDOWN).unwrap();
+    assert_eq!(program.words.len(), 6);
+    assert_eq!(program.labels["loop"], 2);
+    assert_eq!(program.labels["done"], 5);
+    assert_eq!(program.labels["end"], 5);
+}
+
+#[test]
+fn hotspots() {
+    let (processor, program) = run(COUNTDOWN);
+    let profile = Profile::new(processor.stats(), processor.memory(), &program.labels);
+    let hotspots = profile.hotspots();
+    assert_eq!(hotspots.len(), 6);
+    // sub runs 3 times at 5 cycles each
+    assert_eq!((hotspots[0].start, hotspots[0].cycles), (2, 15));
+    assert_eq!(hotspots[0].instructions, 3);
+    let total: u64 = hotspots.iter().map(|x| x.cycles).sum();
+    assert_eq!(total + 1, processor.stats().cycles);
+    let regions = profile.regions();
+    let names: Vec<&str> = regions.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(names, ["loop", "(start)", "done, end"]);
+    assert_eq!((regions[0].start, regions[0].end), (2, 4));
+    assert_eq!(regions[0].instructions, 8);
+    assert_eq!(
+        regions.iter().map(|x| x.cycles).sum::<u64>(),
+        total,
+        "every cycle belongs to a region"
+    );
+}
+
+#[test]
+fn annotated_listing() {
+    let (processor, program) = run(COUNTDOWN);
+    let profile = Profile::new(processor.stats(), processor.memory(), &program.labels);
+    let listing: Vec<String> = profile.annotate().lines().map(String::from).collect();
+    assert_eq!(listing.len(), 9);
+    assert_eq!(listing[2], "loop:");
+    assert_eq!(
+        listing[3],
+        "        15   30.6%         3  0x0002: 0x1223  sub r2, r2, r3"
+    );
+    assert_eq!(&listing[6..8], ["done:", "end:"]);
+    assert_eq!(profile.format_hotspots(1).lines().count(), 2);
+}
@@ -0,0 +1,67 @@
+use simulator::{error::SimError, processor::Processor, snapshot::Snapshot, types::RunState};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+fn sum_countdown() -> Processor {
+    Processor::new_from_file(
+        String::from("../example_bytecode/sum_countdown.ayu"),
+        u64::MAX,
+        false,
+    )
+    .unwrap()
+}
+
+#[test]
+fn resume_mid_instruction() {
+    let mut expected = sum_countdown();
+    let mut trace = Vec::new();
+    while expected.run().unwrap() == RunState::Continue {
+        trace.push(expected.snapshot());
+    }
+
+    // pause every few cycles, save and reload through the text format, then finish the program
+    for snapshot in trace.iter().step_by(7) {
+        let text = snapshot.serialize();
+        let loaded = Snapshot::parse(&text).unwrap();
+        assert_eq!(&loaded, snapshot);
+        let mut processor = Processor::new_from_snapshot(&loaded, false).unwrap();
+        assert_eq!(&processor.snapshot(), snapshot);
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+        assert_eq!(processor.snapshot(), expected.snapshot());
+    }
+}
+
+#[test]
+fn restore_over_running_processor() {
+    let mut processor = sum_countdown();
+    for _ in 0..20 {
+        processor.run().unwrap();
+    }
+    let checkpoint = processor.snapshot();
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(processor.registers()[4], 15);
+    processor.restore(&checkpoint).unwrap();
+    assert_eq!(processor.clock_cycle(), 20);
+    assert_eq!(processor.registers()[4], 0);
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(processor.registers()[4], 15);
+
+    let path = std::env::temp_dir().join("ayu_snapshot_test.snapshot");
+    let path = path.to_str().unwrap();
+    checkpoint.save(path).unwrap();
+    assert_eq!(Snapshot::load(path).unwrap(), checkpoint);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn reject_bad_snapshots() {
+    assert!(Snapshot::parse("hello").is_err());
+    assert!(Snapshot::parse("AYU snapshot version 99\n")
+        .unwrap_err()
+        .to_string()
+        .contains("version"));
+    let error =
+        Snapshot::parse("AYU snapshot version 3\nclock_cycle: 5\nstate: Nowhere\n").unwrap_err();
+    assert!(matches!(error, SimError::Parse { line: 3, .. }));
+}
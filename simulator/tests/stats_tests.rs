@@ -0,0 +1,110 @@
+use simulator::{
+    assembler::assemble,
+    processor::Processor,
+    stats::Stats,
+    types::{ExecutionMode, Opcode, State},
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+/// Copies a word 3 times, branching back twice
+const COPY_LOOP: &str = "
+        setl r2, data
+        setl r4, 3
+        setl r5, 1
+loop:   lw r3, 0(r2)
+        sw r3, 1(r2)
+        sub r4, r4, r5
+        setpcif done, r4, r0
+        setpcif loop, r0, r0
+done:   halt
+data:   .word 0x1234, 0
+";
+
+fn load(source: &str, execution_mode: ExecutionMode) -> Processor {
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    Processor::new_from_array([0; 16], memory, false).with_execution_mode(execution_mode)
+}
+
+fn run(source: &str, execution_mode: ExecutionMode) -> Processor {
+    let mut processor = load(source, execution_mode);
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    processor
+}
+
+#[test]
+fn counters() {
+    let processor = run(COPY_LOOP, ExecutionMode::Cycle);
+    let stats = processor.stats();
+    assert_eq!(stats.instructions, 18);
+    assert_eq!(stats.instruction_fetches, 18);
+    assert_eq!(stats.memory_reads, 3);
+    assert_eq!(stats.memory_writes, 3);
+    assert_eq!(stats.branches_taken, 3);
+    assert_eq!(stats.branches_not_taken, 2);
+    assert_eq!(stats.opcode_count(&Opcode::LoadWord), 3);
+    assert_eq!(stats.opcode_cpi(&Opcode::LoadWord), Some(6.0));
+    assert_eq!(stats.opcode_cpi(&Opcode::SetLower), Some(4.0));
+    assert_eq!(stats.opcode_cpi(&Opcode::Or), None);
+    assert_eq!(stats.pc_histogram[&3], 3);
+    assert_eq!(stats.pc_histogram[&8], 1);
+    // every cycle is spent in a state, the first PcRead belongs to no instruction
+    assert_eq!(stats.state_cycles.iter().sum::<u64>(), stats.cycles);
+    assert_eq!(stats.opcode_cycles.iter().sum::<u64>() + 1, stats.cycles);
+    assert_eq!(stats.state_count(&State::MemoryRead), 3);
+    assert_eq!(stats.cpi(), Some(stats.cycles as f64 / 18.0));
+}
+
+#[test]
+fn modes_count_the_same() {
+    let cycle = run(COPY_LOOP, ExecutionMode::Cycle).stats().clone();
+    let functional = run(COPY_LOOP, ExecutionMode::Functional).stats().clone();
+    // functional mode does not pass through the states
+    assert_eq!(functional.state_cycles, [0; 20]);
+    assert_eq!(
+        functional,
+        Stats {
+            state_cycles: [0; 20],
+            ..cycle
+        }
+    );
+}
+
+#[test]
+fn replayed_cycles_are_not_counted_again() {
+    let mut expected = load(COPY_LOOP, ExecutionMode::Cycle);
+    expected.run_cycles(40).unwrap();
+    let mut processor = load(COPY_LOOP, ExecutionMode::Cycle);
+    processor.set_history_limit(100);
+    processor.run_cycles(40).unwrap();
+    for _ in 0..20 {
+        assert!(processor.step_back());
+    }
+    processor.run_cycles(20).unwrap();
+    assert_eq!(processor.clock_cycle(), expected.clock_cycle());
+    assert_eq!(processor.stats(), expected.stats());
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(
+        processor.stats(),
+        run(COPY_LOOP, ExecutionMode::Cycle).stats()
+    );
+}
+
+#[test]
+fn restored_snapshot_counts_the_instruction_in_progress() {
+    let mut processor = load(COPY_LOOP, ExecutionMode::Cycle);
+    // part way through the second instruction
+    processor.run_cycles(6).unwrap();
+    let snapshot = processor.snapshot();
+    let mut restored = Processor::new_from_snapshot(&snapshot, false).unwrap();
+    restored.run_until_halt(MAX_CYCLES).unwrap();
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    assert_eq!(
+        restored.stats().instructions,
+        processor.stats().instructions - 1
+    );
+}
@@ -0,0 +1,91 @@
+use simulator::{
+    assembler::assemble,
+    bus::Device,
+    devices::{Timer, TIMER_BASE, TIMER_COUNTER, TIMER_EXPIRED, TIMER_RELOAD, TIMER_STATUS},
+    processor::Processor,
+    types::ExecutionMode,
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+/// Run a program with a timer mapped until it halts
+fn run(source: &str, execution_mode: ExecutionMode) -> Processor {
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    let mut processor = Processor::new_from_array([0; 16], memory, false)
+        .with_execution_mode(execution_mode)
+        .with_device(TIMER_BASE, Box::new(Timer::new()))
+        .unwrap();
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    processor
+}
+
+#[test]
+fn counts_every_clock_cycle() {
+    let source = "setl r2, 0x10\nsetu r2, 0xFF\nlw r3, 0(r2)\nlw r4, 0(r2)\nhalt";
+    for mode in [ExecutionMode::Cycle, ExecutionMode::Functional] {
+        let processor = run(source, mode);
+        // the first load is fetched on cycle 9 and reads memory 3 cycles later, the second
+        // is fetched on cycle 15 and the halt finishes on cycle 23
+        assert_eq!(processor.registers()[3], 12, "{:?}", mode);
+        assert_eq!(processor.registers()[4], 18, "{:?}", mode);
+        assert_eq!(processor.peek(TIMER_BASE), 23, "{:?}", mode);
+    }
+}
+
+#[test]
+fn delay_program() {
+    let source = std::fs::read_to_string("../example_bytecode/timer_delay.ayu").unwrap();
+    let cycle = run(&source, ExecutionMode::Cycle);
+    let functional = run(&source, ExecutionMode::Functional);
+    assert!(cycle.clock_cycle() > 100);
+    assert_eq!(cycle.clock_cycle(), functional.clock_cycle());
+    assert_eq!(cycle.registers(), functional.registers());
+    assert_eq!(cycle.registers()[5], TIMER_EXPIRED);
+    assert_eq!(cycle.peek(TIMER_BASE + TIMER_STATUS), 0);
+}
+
+#[test]
+fn registers() {
+    let mut timer = Timer::new();
+    assert_eq!(timer.size(), 4);
+    timer.tick();
+    timer.tick();
+    assert_eq!(timer.read(TIMER_COUNTER), 2);
+    // a free running counter wraps without expiring
+    timer.write(TIMER_COUNTER, 0xFFFF);
+    timer.tick();
+    assert_eq!(timer.read(TIMER_COUNTER), 0);
+    assert_eq!(timer.read(TIMER_STATUS), 0);
+    timer.write(TIMER_RELOAD, 3);
+    assert_eq!(timer.peek(TIMER_RELOAD), 3);
+    (0..3).for_each(|_| timer.tick());
+    assert_eq!(timer.read(TIMER_COUNTER), 0);
+    assert_eq!(timer.read(TIMER_STATUS), TIMER_EXPIRED);
+    timer.tick();
+    assert_eq!(timer.read(TIMER_COUNTER), 1);
+    assert_eq!(timer.read(TIMER_STATUS), TIMER_EXPIRED);
+    timer.write(TIMER_STATUS, 0);
+    assert_eq!(timer.read(TIMER_STATUS), 0);
+}
+
+#[test]
+fn no_stepping_back_while_mapped() {
+    let source = "setl r2, 0x10\nsetu r2, 0xFF\nlw r3, 0(r2)\nhalt";
+    let mut processor = Processor::new_from_array([0; 16], [0; 65536], false)
+        .with_device(TIMER_BASE, Box::new(Timer::new()))
+        .unwrap();
+    for (i, word) in assemble(source).unwrap().into_iter().enumerate() {
+        processor.set_memory(i as u16, word);
+    }
+    processor.set_history_limit(100);
+    processor.run_cycles(10).unwrap();
+    // the timer would keep counting from where it got to
+    assert!(processor.has_stateful_devices());
+    assert_eq!(processor.history_length(), 0);
+    assert!(!processor.step_back());
+    assert_eq!(processor.clock_cycle(), 10);
+}
@@ -0,0 +1,112 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+use simulator::{
+    assembler::assemble,
+    processor::Processor,
+    trace::{Trace, TraceFormat, CSV_HEADER},
+    types::ExecutionMode,
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+const COPY_WORD: &str = "
+        setl r2, data
+        lw r3, 0(r2)
+        sw r3, 1(r2)
+        halt
+data:   .word 0x1234, 0
+";
+
+/// A writer that can still be read after the trace owning it has been dropped
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run a program to completion, returning the lines of it's trace
+fn trace(source: &str, format: TraceFormat, execution_mode: ExecutionMode) -> Vec<String> {
+    trace_with(source, format, execution_mode, |processor| {
+        processor.run_until_halt(MAX_CYCLES).unwrap();
+    })
+}
+
+/// Run a program however a test likes, returning the lines of it's trace
+fn trace_with(
+    source: &str,
+    format: TraceFormat,
+    execution_mode: ExecutionMode,
+    run: impl FnOnce(&mut Processor),
+) -> Vec<String> {
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    let mut processor =
+        Processor::new_from_array([0; 16], memory, false).with_execution_mode(execution_mode);
+    let buffer = SharedBuffer::default();
+    processor.set_trace(Some(Trace::new(format, Box::new(buffer.clone())).unwrap()));
+    run(&mut processor);
+    let text = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+    text.lines().map(String::from).collect()
+}
+
+#[test]
+fn json_lines() {
+    let lines = trace(COPY_WORD, TraceFormat::JsonLines, ExecutionMode::Cycle);
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+        lines[1],
+        "{\"cycle\":5,\"pc\":\"0x0001\",\"instruction\":\"0xB032\",\"opcode\":\"lw\",\
+         \"operands\":[\"r3\",\"0(r2)\"],\"register_writes\":[{\"register\":1,\"value\":\"0x0002\"},\
+         {\"register\":3,\"value\":\"0x1234\"}],\"memory_reads\":[{\"address\":\"0x0004\",\
+         \"value\":\"0x1234\"}],\"memory_writes\":[]}"
+    );
+    assert!(lines[3].contains("\"opcode\":\"halt\""));
+}
+
+#[test]
+fn csv() {
+    let lines = trace(COPY_WORD, TraceFormat::Csv, ExecutionMode::Cycle);
+    assert_eq!(lines[0], CSV_HEADER);
+    assert_eq!(
+        lines[3],
+        "11,0x0002,0xC132,sw,r3 1(r2),r1=0x0003,,0x0005=0x1234"
+    );
+}
+
+#[test]
+fn modes_trace_the_same() {
+    for format in [TraceFormat::JsonLines, TraceFormat::Csv] {
+        assert_eq!(
+            trace(COPY_WORD, format, ExecutionMode::Functional),
+            trace(COPY_WORD, format, ExecutionMode::Cycle)
+        );
+    }
+}
+
+#[test]
+fn replayed_instructions_are_traced_once() {
+    for execution_mode in [ExecutionMode::Cycle, ExecutionMode::Functional] {
+        let lines = trace_with(COPY_WORD, TraceFormat::Csv, execution_mode, |processor| {
+            processor.set_history_limit(100);
+            processor.run_cycles(12).unwrap();
+            assert!(processor.step_back_instruction());
+            assert!(processor.step_back_instruction());
+            // part way back into an instruction
+            assert!(processor.step_back());
+            processor.run_until_halt(MAX_CYCLES).unwrap();
+        });
+        assert_eq!(lines, trace(COPY_WORD, TraceFormat::Csv, execution_mode));
+    }
+}
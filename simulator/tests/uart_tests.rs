@@ -0,0 +1,154 @@
+use std::cell::RefCell;
+use std::io::{Cursor, Read, Write};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use simulator::{
+    assembler::assemble,
+    bus::Device,
+    debugger::Debugger,
+    devices::{Uart, RX_AVAILABLE, RX_ENDED, TX_READY, UART_BASE, UART_DATA, UART_STATUS},
+    processor::Processor,
+    types::ExecutionMode,
+};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+/// A writer that can still be read after the UART owning it has been dropped
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Input that arrives whenever the test sends it, and ends once the sender is dropped
+struct ChannelReader(Receiver<Vec<u8>>);
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.recv() {
+            Ok(bytes) => {
+                buf[..bytes.len()].copy_from_slice(&bytes);
+                Ok(bytes.len())
+            }
+            Err(_) => Ok(0),
+        }
+    }
+}
+
+/// Read the UART status until it has some bits set, failing if that takes too long
+fn wait_for_status(uart: &mut Uart, bits: u16) -> u16 {
+    let start = Instant::now();
+    loop {
+        let status = uart.read(UART_STATUS);
+        if status & bits == bits {
+            return status;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "status stuck at {status:#05b}"
+        );
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Run the UART echo example with some input, returning what it transmitted
+fn echo(input: &[u8], execution_mode: ExecutionMode) -> Vec<u8> {
+    let source = std::fs::read_to_string("../example_bytecode/uart_echo.ayu").unwrap();
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(&source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    let output = SharedBuffer::default();
+    let uart = Uart::scripted(input, Box::new(output.clone()));
+    let mut processor = Processor::new_from_array([0; 16], memory, false)
+        .with_execution_mode(execution_mode)
+        .with_device(UART_BASE, Box::new(uart))
+        .unwrap();
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    let transmitted = output.0.borrow().clone();
+    transmitted
+}
+
+#[test]
+fn echo_program() {
+    for mode in [ExecutionMode::Cycle, ExecutionMode::Functional] {
+        assert_eq!(echo(b"Hello\n", mode), b"Hello\n");
+        assert_eq!(echo(b"", mode), b"");
+    }
+}
+
+#[test]
+fn registers() {
+    let output = SharedBuffer::default();
+    let mut uart = Uart::scripted(b"ab", Box::new(output.clone()));
+    assert_eq!(uart.size(), 3);
+    // nothing is received until the program asks
+    assert_eq!(uart.peek(UART_STATUS), TX_READY);
+    assert_eq!(uart.read(UART_STATUS), TX_READY | RX_AVAILABLE);
+    assert_eq!(uart.peek(UART_DATA), b'a' as u16);
+    assert_eq!(uart.read(UART_DATA), b'a' as u16);
+    assert_eq!(uart.read(UART_DATA), b'b' as u16);
+    assert_eq!(uart.read(UART_STATUS), TX_READY | RX_ENDED);
+    assert_eq!(uart.read(UART_DATA), 0);
+    uart.write(UART_DATA, 0x1234);
+    uart.write(UART_STATUS, b'x' as u16);
+    assert_eq!(*output.0.borrow(), [0x34]);
+}
+
+#[test]
+fn status_does_not_wait_for_input() {
+    let (sender, receiver) = mpsc::channel();
+    let mut uart = Uart::new(
+        Box::new(ChannelReader(receiver)),
+        Box::new(SharedBuffer::default()),
+    );
+    assert_eq!(uart.read(UART_STATUS), TX_READY);
+    assert_eq!(uart.read(UART_DATA), 0);
+    assert!(!uart.interrupt());
+    sender.send(b"a".to_vec()).unwrap();
+    assert_eq!(
+        wait_for_status(&mut uart, RX_AVAILABLE),
+        TX_READY | RX_AVAILABLE
+    );
+    assert_eq!(uart.read(UART_DATA), b'a' as u16);
+    assert_eq!(uart.read(UART_STATUS), TX_READY);
+    drop(sender);
+    assert_eq!(wait_for_status(&mut uart, RX_ENDED), TX_READY | RX_ENDED);
+}
+
+#[test]
+fn debugger_commands_and_uart_input_are_separate() {
+    let source = std::fs::read_to_string("../example_bytecode/uart_echo.ayu").unwrap();
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(&source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    // the UART reads from its own input in place of stdin
+    let output = SharedBuffer::default();
+    let uart = Uart::new(
+        Box::new(Cursor::new(b"uart\n".to_vec())),
+        Box::new(output.clone()),
+    );
+    let processor = Processor::new_from_array([0; 16], memory, false)
+        .with_device(UART_BASE, Box::new(uart))
+        .unwrap();
+    let mut debugger = Debugger::new(processor);
+    let mut commands_output: Vec<u8> = Vec::new();
+    debugger
+        .run(Cursor::new("c\n"), &mut commands_output)
+        .unwrap();
+    assert_eq!(*output.0.borrow(), b"uart\n");
+    assert!(String::from_utf8(commands_output)
+        .unwrap()
+        .contains("The program has finished"));
+}
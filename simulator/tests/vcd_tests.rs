@@ -0,0 +1,88 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+use simulator::{assembler::assemble, processor::Processor, vcd::VcdWriter};
+
+/// Clock cycles a test program may run for before it is considered stuck
+const MAX_CYCLES: u64 = 10_000;
+
+/// A writer that can still be read after the dump owning it has been dropped
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run a program to completion, returning the lines of it's VCD file
+fn dump(source: &str) -> Vec<String> {
+    let mut memory = [0; 65536];
+    for (i, word) in assemble(source).unwrap().into_iter().enumerate() {
+        memory[i] = word;
+    }
+    let mut processor = Processor::new_from_array([0; 16], memory, false);
+    let buffer = SharedBuffer::default();
+    processor.set_vcd(Some(VcdWriter::new(Box::new(buffer.clone()))));
+    processor.run_until_halt(MAX_CYCLES).unwrap();
+    let text = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+    text.lines().map(String::from).collect()
+}
+
+#[test]
+fn declarations() {
+    let lines = dump("setl r2, 5\nhalt");
+    let definitions = lines
+        .iter()
+        .position(|x| x == "$enddefinitions $end")
+        .unwrap();
+    let variables: Vec<&String> = lines[..definitions]
+        .iter()
+        .filter(|x| x.starts_with("$var"))
+        .collect();
+    assert_eq!(variables.len(), 29);
+    assert!(variables.contains(&&String::from("$var wire 1 ! clock $end")));
+    assert!(variables.contains(&&String::from("$var string 1 \" state $end")));
+    assert!(variables.contains(&&String::from("$var wire 16 8 alu_output $end")));
+}
+
+#[test]
+fn value_changes() {
+    // setl takes 4 cycles after the first PcRead, halt 3 more
+    let lines = dump("setl r2, 5\nhalt");
+    let times: Vec<u64> = lines
+        .iter()
+        .filter_map(|x| x.strip_prefix('#'))
+        .map(|x| x.parse().unwrap())
+        .collect();
+    assert_eq!(times, (0..16).collect::<Vec<u64>>());
+    // only what the fetch cycle changes is written, it loads the instruction and adds 1 to the PC
+    let fetch: Vec<&str> = lines
+        .iter()
+        .skip_while(|x| *x != "#2")
+        .skip(1)
+        .take_while(|x| *x != "#3")
+        .map(String::as_str)
+        .collect();
+    assert_eq!(
+        fetch,
+        [
+            "1!",
+            "sInstructionFetch \"",
+            "b1001001000000101 $",
+            "1(",
+            "1*",
+            "0/",
+            "sAdd 1",
+            "sConstant1 2",
+            "b1001001000000101 5",
+            "b1 8"
+        ]
+    );
+}